@@ -0,0 +1,316 @@
+use super::{Account, BuySellIndicator, FeeAsset, Trade, SECONDS_PER_YEAR};
+use chrono::NaiveDateTime;
+
+const TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, PartialEq)]
+pub enum AuditError {
+    /// a trade with a non-positive quantity or price, or a negative fee
+    InvalidTrade { timestamp: NaiveDateTime },
+    NegativeCash { timestamp: NaiveDateTime, cash: f64 },
+    NegativePosition { timestamp: NaiveDateTime, quantity: f64 },
+    NegativeBnbBalance { timestamp: NaiveDateTime, balance: f64 },
+    /// the replayed cash differs from what the account recorded
+    CashMismatch { timestamp: NaiveDateTime, expected: f64, actual: f64 },
+    /// the replayed position differs from what the account recorded
+    PositionMismatch { timestamp: NaiveDateTime, expected: f64, actual: f64 },
+    /// the replayed cumulative fees differ from what the account recorded
+    FeeMismatch { timestamp: NaiveDateTime, expected: f64, actual: f64 },
+    /// the interest the cash yield should have paid differs from what the account recorded
+    InterestMismatch { timestamp: NaiveDateTime, expected: f64, actual: f64 },
+    /// the staking rewards the staking yield should have paid differ from what the account recorded
+    StakingMismatch { timestamp: NaiveDateTime, expected: f64, actual: f64 },
+    /// cash plus position value differs from the recorded equity
+    EquityMismatch { timestamp: NaiveDateTime, expected: f64, actual: f64 },
+}
+
+fn differs(expected: f64, actual: f64) -> bool {
+    let difference = (expected - actual).abs();
+    difference.is_nan() || difference > TOLERANCE * (1. + expected.abs())
+}
+
+/// cash, position and BNB rebuilt from the trade history, cash flows and yield models alone
+struct Replay {
+    cash: f64,
+    quantity: f64,
+    bnb_balance: f64,
+    fees: f64,
+    interest_earned: f64,
+    staking_rewards: f64,
+}
+
+impl Replay {
+    /// pay the yield on what was held at `to`, at the rates in force at `from`, as marking the account does
+    fn accrue_yield(&mut self, account: &Account, from: NaiveDateTime, to: NaiveDateTime) {
+        let years = (to - from).num_seconds() as f64 / SECONDS_PER_YEAR;
+        if years <= 0. {
+            return;
+        }
+        let interest = self.cash.max(0.) * account.cash_yield.apr(from) * years;
+        self.cash += interest;
+        self.interest_earned += interest;
+        let reward = self.quantity.max(0.) * account.staking_yield.apr(from) * years;
+        self.quantity += reward;
+        self.staking_rewards += reward;
+    }
+
+    fn apply_trade(&mut self, trade: &Trade) {
+        let notional = trade.price * trade.quantity;
+        self.fees += trade.fee;
+        let cash_fee = match trade.fee_asset {
+            FeeAsset::Quote => trade.fee,
+            // taken from the bought quantity, or from the proceeds on a sale
+            FeeAsset::Base => match trade.buy_sell_indicator {
+                BuySellIndicator::Buy => 0.,
+                BuySellIndicator::Sell => trade.fee,
+            },
+            FeeAsset::Bnb { price, .. } => {
+                self.bnb_balance -= trade.fee / price;
+                0.
+            }
+        };
+        match trade.buy_sell_indicator {
+            BuySellIndicator::Buy => {
+                self.cash -= notional + cash_fee;
+                self.quantity += match trade.fee_asset {
+                    FeeAsset::Base => trade.quantity - trade.fee / trade.price,
+                    _ => trade.quantity,
+                };
+            }
+            BuySellIndicator::Sell => {
+                self.cash += notional - cash_fee;
+                self.quantity -= trade.quantity;
+            }
+        }
+    }
+}
+
+impl Account {
+    /// replay the trade history, cash flows and yield, checking them against every point of the equity curve
+    pub fn audit(&self) -> Result<(), Vec<AuditError>> {
+        let mut errors = Vec::new();
+        let first = &self.equity_history[0];
+        let mut replay = Replay {
+            cash: first.cash,
+            quantity: first.quantity,
+            bnb_balance: self.initial_bnb_balance,
+            fees: first.fees,
+            interest_earned: first.interest_earned,
+            staking_rewards: first.staking_rewards,
+        };
+        let bnb_price = match self.fee_asset {
+            FeeAsset::Bnb { price, .. } => price,
+            _ => 0.,
+        };
+
+        for trade in self.trade_history.iter() {
+            if !(trade.quantity > 0. && trade.price > 0. && trade.fee >= 0.) {
+                errors.push(AuditError::InvalidTrade {
+                    timestamp: trade.timestamp,
+                });
+            }
+        }
+
+        let mut trades = self.trade_history.iter().peekable();
+        let mut flows = self.cash_flows.iter().peekable();
+        let mut previous = first;
+        for point in self.equity_history.iter() {
+            while let Some(flow) = flows.next_if(|flow| flow.timestamp <= point.timestamp) {
+                replay.cash += flow.amount;
+            }
+            while let Some(trade) = trades.next_if(|trade| trade.timestamp <= point.timestamp) {
+                replay.apply_trade(trade);
+            }
+            replay.accrue_yield(self, previous.timestamp, point.timestamp);
+            previous = point;
+
+            let timestamp = point.timestamp;
+            if differs(replay.cash, point.cash) {
+                errors.push(AuditError::CashMismatch {
+                    timestamp,
+                    expected: replay.cash,
+                    actual: point.cash,
+                });
+            }
+            if differs(replay.quantity, point.quantity) {
+                errors.push(AuditError::PositionMismatch {
+                    timestamp,
+                    expected: replay.quantity,
+                    actual: point.quantity,
+                });
+            }
+            if differs(replay.fees, point.fees) {
+                errors.push(AuditError::FeeMismatch {
+                    timestamp,
+                    expected: replay.fees,
+                    actual: point.fees,
+                });
+            }
+            if differs(replay.interest_earned, point.interest_earned) {
+                errors.push(AuditError::InterestMismatch {
+                    timestamp,
+                    expected: replay.interest_earned,
+                    actual: point.interest_earned,
+                });
+            }
+            if differs(replay.staking_rewards, point.staking_rewards) {
+                errors.push(AuditError::StakingMismatch {
+                    timestamp,
+                    expected: replay.staking_rewards,
+                    actual: point.staking_rewards,
+                });
+            }
+            if differs(point.quantity * point.price, point.market_value) {
+                errors.push(AuditError::EquityMismatch {
+                    timestamp,
+                    expected: point.quantity * point.price,
+                    actual: point.market_value,
+                });
+            }
+            let equity = replay.cash + replay.quantity * point.price + replay.bnb_balance * bnb_price;
+            if differs(equity, point.equity) {
+                errors.push(AuditError::EquityMismatch {
+                    timestamp,
+                    expected: equity,
+                    actual: point.equity,
+                });
+            }
+            if replay.cash < -TOLERANCE {
+                errors.push(AuditError::NegativeCash {
+                    timestamp,
+                    cash: replay.cash,
+                });
+            }
+            if replay.quantity < -TOLERANCE {
+                errors.push(AuditError::NegativePosition {
+                    timestamp,
+                    quantity: replay.quantity,
+                });
+            }
+            if replay.bnb_balance < -TOLERANCE {
+                errors.push(AuditError::NegativeBnbBalance {
+                    timestamp,
+                    balance: replay.bnb_balance,
+                });
+            }
+        }
+
+        // anything after the last mark must still add up to the current balances, yield only accrues on a mark
+        for flow in flows {
+            replay.cash += flow.amount;
+        }
+        for trade in trades {
+            replay.apply_trade(trade);
+        }
+        if differs(replay.interest_earned, self.interest_earned) {
+            errors.push(AuditError::InterestMismatch {
+                timestamp: self.last_accrual,
+                expected: replay.interest_earned,
+                actual: self.interest_earned,
+            });
+        }
+        if differs(replay.staking_rewards, self.staking_rewards) {
+            errors.push(AuditError::StakingMismatch {
+                timestamp: self.last_accrual,
+                expected: replay.staking_rewards,
+                actual: self.staking_rewards,
+            });
+        }
+        if differs(replay.cash, self.available_fund) {
+            errors.push(AuditError::CashMismatch {
+                timestamp: self.last_accrual,
+                expected: replay.cash,
+                actual: self.available_fund,
+            });
+        }
+        if differs(replay.quantity, self.position.quantity) {
+            errors.push(AuditError::PositionMismatch {
+                timestamp: self.last_accrual,
+                expected: replay.quantity,
+                actual: self.position.quantity,
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{Position, YieldModel};
+    use chrono::{Duration, NaiveDate};
+
+    fn traded_account() -> Account {
+        let start = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let position = Position { quantity: 0., cost: 0. };
+        let mut account = Account::new(1000., position, start);
+        account.open(start + Duration::hours(1), 4., 100., 0.4, 0.);
+        account.mark_to_market(start + Duration::hours(1), 110.);
+        account.deposit(start + Duration::hours(2), 100.);
+        account.close(start + Duration::hours(2), 2., 120., 0.24, 0.);
+        account.mark_to_market(start + Duration::hours(2), 120.);
+        account
+    }
+
+    #[test]
+    fn test_audit_clean_account() {
+        assert_eq!(traded_account().audit(), Ok(()));
+    }
+
+    #[test]
+    fn test_audit_detects_created_money() {
+        let mut account = traded_account();
+        account.available_fund += 50.;
+        let errors = account.audit().unwrap_err();
+        assert!(matches!(errors[0], AuditError::CashMismatch { expected, actual, .. } if actual - expected == 50.));
+    }
+
+    fn yielding_account() -> Account {
+        let start = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let position = Position { quantity: 0., cost: 0. };
+        let mut account = Account::new(1000., position, start);
+        account.cash_yield = YieldModel::FlatApr(0.05);
+        account.staking_yield = YieldModel::FlatApr(0.1);
+        account.open(start + Duration::days(30), 4., 100., 0.4, 0.);
+        account.mark_to_market(start + Duration::days(30), 110.);
+        account.mark_to_market(start + Duration::days(60), 120.);
+        account
+    }
+
+    #[test]
+    fn test_audit_recomputes_yield() {
+        let account = yielding_account();
+        assert!(account.interest_earned > 0. && account.staking_rewards > 0.);
+        assert_eq!(account.audit(), Ok(()));
+    }
+
+    #[test]
+    fn test_audit_detects_wrong_yield() {
+        // as if the accrual had paid twice the cash rate
+        let mut account = yielding_account();
+        account.cash_yield = YieldModel::FlatApr(0.025);
+        let errors = account.audit().unwrap_err();
+        let interest = errors.iter().find(|error| matches!(error, AuditError::InterestMismatch { .. }));
+        assert!(matches!(interest, Some(AuditError::InterestMismatch { expected, actual, .. }) if (actual - 2. * expected).abs() < 1e-9));
+
+        let mut account = yielding_account();
+        account.staking_yield = YieldModel::Zero;
+        let errors = account.audit().unwrap_err();
+        assert!(errors.iter().any(|error| matches!(error, AuditError::StakingMismatch { expected, .. } if *expected == 0.)));
+    }
+
+    #[test]
+    fn test_audit_detects_invalid_trade() {
+        let mut account = traded_account();
+        account.trade_history[0].fee = -1.;
+        let errors = account.audit().unwrap_err();
+        assert!(errors.contains(&AuditError::InvalidTrade {
+            timestamp: account.trade_history[0].timestamp
+        }));
+    }
+}
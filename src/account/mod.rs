@@ -0,0 +1,990 @@
+mod audit;
+
+pub use audit::AuditError;
+
+use crate::data::RejectReason;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use log::debug;
+
+pub const SECONDS_PER_YEAR: f64 = 365.25 * 24. * 60. * 60.;
+
+pub struct Account {
+    pub available_fund: f64,
+    pub position: Position,
+    pub profit_and_loss_history: Vec<TimeValue>,
+    pub trade_history: Vec<Trade>,
+    pub rejections: Vec<Rejection>,
+    pub cash_flows: Vec<CashFlow>,
+    pub cash_yield: YieldModel,
+    pub staking_yield: YieldModel,
+    pub interest_earned: f64,
+    pub staking_rewards: f64,
+    pub fee_asset: FeeAsset,
+    pub bnb_balance: f64,
+    pub fees_paid: f64,
+    /// the smallest position the exchange lets us sell, what a sale leaves below it is dust and counts as flat
+    pub dust: f64,
+    scheduled_cash_flows: Vec<CashFlow>,
+    start_timestamp: NaiveDateTime,
+    initial_capital: f64,
+    last_price: f64,
+    growth: f64,
+    period_start_value: f64,
+    last_accrual: NaiveDateTime,
+    initial_bnb_balance: f64,
+    equity_history: Vec<EquityPoint>,
+}
+
+/// the account as marked at the end of a bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EquityPoint {
+    pub timestamp: NaiveDateTime,
+    pub cash: f64,
+    pub quantity: f64,
+    pub price: f64,
+    /// the position valued at `price`
+    pub market_value: f64,
+    /// cash, position and any BNB held for fees
+    pub equity: f64,
+    /// fees paid so far, valued in the quote currency
+    pub fees: f64,
+    pub interest_earned: f64,
+    pub staking_rewards: f64,
+    /// what one unit invested at the start had grown to, deposits and withdrawals chained out
+    pub growth: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TimeValue {
+    timestamp: NaiveDateTime,
+    realised_pnl: f64,
+    unrealised_pnl: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Position {
+    pub quantity: f64,
+    pub cost: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Trade {
+    timestamp: NaiveDateTime,
+    buy_sell_indicator: BuySellIndicator,
+    quantity: f64,
+    price: f64,
+    fee: f64,
+    fee_asset: FeeAsset,
+    slippage: f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Rejection {
+    timestamp: NaiveDateTime,
+    buy_sell_indicator: BuySellIndicator,
+    quantity: f64,
+    price: f64,
+    reason: RejectReason,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BuySellIndicator {
+    Buy,
+    Sell,
+}
+
+/// the asset trading fees are paid with, fees are always quoted in the quote currency
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FeeAsset {
+    Quote,
+    /// deducted from the base asset, so buys receive less than the traded quantity
+    Base,
+    /// paid from a BNB balance at a discount, falling back to quote once the balance runs out
+    Bnb { discount: f64, price: f64 },
+}
+
+/// external money moving in (positive amount) or out (negative amount) of the account
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CashFlow {
+    pub timestamp: NaiveDateTime,
+    pub amount: f64,
+}
+
+impl CashFlow {
+    /// the same amount on the first day of every month from `from` until before `to`
+    pub fn monthly(from: NaiveDate, to: NaiveDate, amount: f64) -> Vec<CashFlow> {
+        CashFlow::every(Frequency::Monthly, from, to, amount)
+    }
+
+    /// the same amount at the start of every period from `from` until before `to`
+    pub fn every(frequency: Frequency, from: NaiveDate, to: NaiveDate, amount: f64) -> Vec<CashFlow> {
+        let mut date = frequency.first_from(from);
+        let mut flows = Vec::new();
+        while date < to {
+            flows.push(CashFlow {
+                timestamp: date.and_hms(0, 0, 0),
+                amount,
+            });
+            date = frequency.next(date);
+        }
+        flows
+    }
+}
+
+/// how often a schedule repeats, every period starts at midnight
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Hash, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Frequency {
+    Daily,
+    /// starting on Mondays
+    Weekly,
+    #[default]
+    Monthly,
+}
+
+impl Frequency {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "daily",
+            Frequency::Weekly => "weekly",
+            Frequency::Monthly => "monthly",
+        }
+    }
+
+    /// the start of the period `timestamp` falls in
+    pub fn period_start(&self, timestamp: NaiveDateTime) -> NaiveDate {
+        let date = timestamp.date();
+        match self {
+            Frequency::Daily => date,
+            Frequency::Weekly => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Frequency::Monthly => NaiveDate::from_ymd(date.year(), date.month(), 1),
+        }
+    }
+
+    /// the first period start on or after `date`
+    fn first_from(&self, date: NaiveDate) -> NaiveDate {
+        let start = self.period_start(date.and_hms(0, 0, 0));
+        if start < date {
+            self.next(start)
+        } else {
+            start
+        }
+    }
+
+    /// the period start following the period start `date`
+    fn next(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Frequency::Daily => date + Duration::days(1),
+            Frequency::Weekly => date + Duration::days(7),
+            Frequency::Monthly => next_month(date),
+        }
+    }
+}
+
+/// an annual percentage rate, either flat or changing over time
+#[derive(Debug, PartialEq, Clone, Default)]
+pub enum YieldModel {
+    #[default]
+    Zero,
+    FlatApr(f64),
+    /// each rate applies from its timestamp until the next one, sorted by timestamp
+    TimeSeries(Vec<(NaiveDateTime, f64)>),
+}
+
+impl YieldModel {
+    pub fn apr(&self, timestamp: NaiveDateTime) -> f64 {
+        match self {
+            YieldModel::Zero => 0.,
+            YieldModel::FlatApr(apr) => *apr,
+            YieldModel::TimeSeries(rates) => rates
+                .iter()
+                .take_while(|(from, _)| *from <= timestamp)
+                .last()
+                .map_or(0., |(_, apr)| *apr),
+        }
+    }
+}
+
+fn next_month(date: NaiveDate) -> NaiveDate {
+    if date.month() < 12 {
+        NaiveDate::from_ymd(date.year(), date.month() + 1, 1)
+    } else {
+        NaiveDate::from_ymd(date.year() + 1, 1, 1)
+    }
+}
+
+impl TimeValue {
+    pub fn timestamp(&self) -> NaiveDateTime {
+        self.timestamp
+    }
+
+    pub fn realised_pnl(&self) -> f64 {
+        self.realised_pnl
+    }
+
+    pub fn unrealised_pnl(&self) -> f64 {
+        self.unrealised_pnl
+    }
+
+    pub fn total_pnl(&self) -> f64 {
+        self.realised_pnl + self.unrealised_pnl
+    }
+}
+
+impl Rejection {
+    pub fn timestamp(&self) -> NaiveDateTime {
+        self.timestamp
+    }
+
+    pub fn buy_sell_indicator(&self) -> BuySellIndicator {
+        self.buy_sell_indicator
+    }
+
+    /// the quantity that was not traded
+    pub fn quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+
+    pub fn reason(&self) -> RejectReason {
+        self.reason
+    }
+}
+
+impl Trade {
+    pub fn timestamp(&self) -> NaiveDateTime {
+        self.timestamp
+    }
+
+    pub fn buy_sell_indicator(&self) -> BuySellIndicator {
+        self.buy_sell_indicator
+    }
+
+    pub fn quantity(&self) -> f64 {
+        self.quantity
+    }
+
+    pub fn price(&self) -> f64 {
+        self.price
+    }
+
+    /// the whole fee in the quote currency, whichever asset paid it
+    pub fn fee(&self) -> f64 {
+        self.fee
+    }
+
+    /// what the fill cost against the quoted price, in the quote currency
+    pub fn slippage(&self) -> f64 {
+        self.slippage
+    }
+
+    /// the quantity that actually changed hands once a base asset fee on a buy is taken off
+    pub fn received_quantity(&self) -> f64 {
+        match (self.buy_sell_indicator, self.fee_asset) {
+            (BuySellIndicator::Buy, FeeAsset::Base) => self.quantity - self.fee / self.price,
+            _ => self.quantity,
+        }
+    }
+
+    /// the part of the fee paid on top of the traded quantity, in the quote currency
+    pub fn fee_cost(&self) -> f64 {
+        match (self.buy_sell_indicator, self.fee_asset) {
+            (BuySellIndicator::Buy, FeeAsset::Base) => 0.,
+            _ => self.fee,
+        }
+    }
+}
+
+impl Account {
+    pub fn new(fund: f64, initial_position: Position, start_timestamp: NaiveDateTime) -> Account {
+        let initial_pnl = TimeValue {
+            timestamp: start_timestamp,
+            realised_pnl: 0.,
+            unrealised_pnl: 0.,
+        };
+        let initial_capital = fund + initial_position.quantity * initial_position.cost;
+        let last_price = initial_position.cost;
+        let initial_point = EquityPoint {
+            timestamp: start_timestamp,
+            cash: fund,
+            quantity: initial_position.quantity,
+            price: last_price,
+            market_value: initial_position.quantity * last_price,
+            equity: initial_capital,
+            fees: 0.,
+            interest_earned: 0.,
+            staking_rewards: 0.,
+            growth: 1.,
+        };
+        Account {
+            available_fund: fund,
+            position: initial_position,
+            profit_and_loss_history: vec![initial_pnl],
+            trade_history: Vec::new(),
+            rejections: Vec::new(),
+            cash_flows: Vec::new(),
+            cash_yield: YieldModel::Zero,
+            staking_yield: YieldModel::Zero,
+            interest_earned: 0.,
+            staking_rewards: 0.,
+            fee_asset: FeeAsset::Quote,
+            bnb_balance: 0.,
+            fees_paid: 0.,
+            dust: 0.,
+            scheduled_cash_flows: Vec::new(),
+            start_timestamp,
+            initial_capital,
+            last_price,
+            growth: 1.,
+            period_start_value: initial_capital,
+            last_accrual: start_timestamp,
+            initial_bnb_balance: 0.,
+            equity_history: vec![initial_point],
+        }
+    }
+
+    /// one point for the opening balance and one per marked bar
+    pub fn equity_curve(&self) -> &[EquityPoint] {
+        &self.equity_history
+    }
+
+    pub fn equity(&self) -> f64 {
+        self.available_fund + self.position.quantity * self.last_price + self.bnb_value()
+    }
+
+    fn bnb_value(&self) -> f64 {
+        match self.fee_asset {
+            FeeAsset::Bnb { price, .. } => self.bnb_balance * price,
+            _ => 0.,
+        }
+    }
+
+    /// pay fees from `balance` BNB converted at `price`, counting the BNB as part of the starting capital
+    pub fn pay_fees_in_bnb(&mut self, balance: f64, price: f64, discount: f64) {
+        self.initial_capital -= self.bnb_value();
+        self.period_start_value -= self.bnb_value();
+        self.fee_asset = FeeAsset::Bnb { discount, price };
+        self.bnb_balance = balance;
+        self.initial_bnb_balance = balance;
+        self.initial_capital += self.bnb_value();
+        self.period_start_value += self.bnb_value();
+    }
+
+    /// the asset a fee of this size would actually be paid with
+    pub fn fee_asset_for(&self, fee: f64) -> FeeAsset {
+        match self.fee_asset {
+            FeeAsset::Bnb { discount, price } if self.bnb_balance * price < fee * (1. - discount) => FeeAsset::Quote,
+            fee_asset => fee_asset,
+        }
+    }
+
+    /// traded notional within `window` up to `timestamp`, as used for VIP fee tiers
+    pub fn rolling_volume(&self, timestamp: NaiveDateTime, window: Duration) -> f64 {
+        self.trade_history
+            .iter()
+            .rev()
+            .take_while(|trade| trade.timestamp > timestamp - window)
+            .map(|trade| trade.quantity * trade.price)
+            .sum()
+    }
+
+    /// settle a fee quoted in the quote currency, returning the fee actually paid and the cash part of it
+    fn charge_fee(&mut self, fee: f64) -> (FeeAsset, f64, f64) {
+        let fee_asset = self.fee_asset_for(fee);
+        let (fee, cash_fee) = match fee_asset {
+            FeeAsset::Quote => (fee, fee),
+            FeeAsset::Base => (fee, 0.),
+            FeeAsset::Bnb { discount, price } => {
+                let fee = fee * (1. - discount);
+                self.bnb_balance -= fee / price;
+                (fee, 0.)
+            }
+        };
+        self.fees_paid += fee;
+        (fee_asset, fee, cash_fee)
+    }
+
+    pub fn net_contribution(&self) -> f64 {
+        self.initial_capital + self.cash_flows.iter().map(|flow| flow.amount).sum::<f64>()
+    }
+
+    pub fn schedule_cash_flows(&mut self, flows: Vec<CashFlow>) {
+        self.scheduled_cash_flows.extend(flows);
+        self.scheduled_cash_flows.sort_by_key(|flow| flow.timestamp);
+    }
+
+    /// apply every scheduled cash flow that is due by `timestamp`
+    pub fn process_cash_flows(&mut self, timestamp: NaiveDateTime) {
+        let due = self
+            .scheduled_cash_flows
+            .iter()
+            .take_while(|flow| flow.timestamp <= timestamp)
+            .count();
+        let flows: Vec<CashFlow> = self.scheduled_cash_flows.drain(..due).collect();
+        for flow in flows {
+            if flow.amount >= 0. {
+                self.deposit(timestamp, flow.amount);
+            } else {
+                self.withdraw(timestamp, -flow.amount);
+            }
+        }
+    }
+
+    pub fn deposit(&mut self, timestamp: NaiveDateTime, amount: f64) {
+        self.apply_cash_flow(timestamp, amount);
+    }
+
+    /// withdraw up to the available fund, returning the amount actually withdrawn
+    pub fn withdraw(&mut self, timestamp: NaiveDateTime, amount: f64) -> f64 {
+        let amount = amount.min(self.available_fund).max(0.);
+        self.apply_cash_flow(timestamp, -amount);
+        amount
+    }
+
+    fn apply_cash_flow(&mut self, timestamp: NaiveDateTime, amount: f64) {
+        debug!("{}, cash flow ${:.02}", timestamp, amount);
+        let value = self.equity();
+        if self.period_start_value > 0. {
+            self.growth *= value / self.period_start_value;
+        }
+        self.available_fund += amount;
+        self.period_start_value = value + amount;
+        self.cash_flows.push(CashFlow { timestamp, amount });
+    }
+
+    /// growth of one unit invested at the start, chained across every deposit and withdrawal so far
+    fn time_weighted_growth(&self) -> f64 {
+        if self.period_start_value > 0. {
+            self.growth * self.equity() / self.period_start_value
+        } else {
+            self.growth
+        }
+    }
+
+    /// profit over the net contribution, `None` when nothing was put in
+    pub fn simple_return(&self) -> Option<f64> {
+        let contribution = self.net_contribution();
+        if contribution > 0. {
+            Some((self.equity() - contribution) / contribution)
+        } else {
+            None
+        }
+    }
+
+    /// return with the effect of deposits and withdrawals chained out
+    pub fn time_weighted_return(&self) -> f64 {
+        self.time_weighted_growth() - 1.
+    }
+
+    /// annualised internal rate of return of the initial capital, cash flows and final equity
+    pub fn money_weighted_return(&self) -> Option<f64> {
+        let end_timestamp = self.profit_and_loss_history.last()?.timestamp;
+        let years = |timestamp: NaiveDateTime| {
+            (timestamp - self.start_timestamp).num_seconds() as f64 / SECONDS_PER_YEAR
+        };
+        let mut flows = vec![(0., -self.initial_capital)];
+        flows.extend(self.cash_flows.iter().map(|flow| (years(flow.timestamp), -flow.amount)));
+        flows.push((years(end_timestamp), self.equity()));
+        if years(end_timestamp) <= 0. {
+            return None;
+        }
+
+        let npv = |rate: f64| -> f64 {
+            flows
+                .iter()
+                .map(|(t, amount)| amount / (1. + rate).powf(*t))
+                .sum()
+        };
+        let (mut low, mut high) = (-0.9999, 1.);
+        while npv(high) > 0. {
+            high *= 2.;
+            if high > 1e6 {
+                return None;
+            }
+        }
+        if npv(low) < 0. {
+            return None;
+        }
+        for _ in 0..200 {
+            let mid = (low + high) / 2.;
+            if npv(mid) > 0. {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Some((low + high) / 2.)
+    }
+
+    fn average_cost(&mut self, quantity: f64, price: f64) -> f64 {
+        (self.position.quantity * self.position.cost + quantity * price) / (self.position.quantity + quantity)
+    }
+
+    pub fn open(&mut self, timestamp: NaiveDateTime, quantity: f64, price: f64, fee: f64, slippage: f64) {
+        let (fee_asset, fee, cash_fee) = self.charge_fee(fee);
+        let received = match fee_asset {
+            FeeAsset::Base => quantity - fee / price,
+            _ => quantity,
+        };
+        self.position.cost = self.average_cost(received, price * quantity / received);
+        self.position.quantity += received;
+        self.available_fund -= price * quantity + cash_fee;
+
+        self.trade_history.push(Trade {
+            timestamp,
+            buy_sell_indicator: BuySellIndicator::Buy,
+            quantity,
+            price,
+            fee,
+            fee_asset,
+            slippage,
+        });
+    }
+
+    pub fn close(&mut self, timestamp: NaiveDateTime, quantity: f64, price: f64, fee: f64, slippage: f64) {
+        let last_pnl = self.profit_and_loss_history.last().unwrap();
+        let current_pnl = quantity * (price - self.position.cost);
+        let realised_pnl = last_pnl.realised_pnl + current_pnl;
+        let unrealised_pnl = last_pnl.unrealised_pnl - current_pnl;
+        let new_pnl = TimeValue {
+            timestamp,
+            realised_pnl,
+            unrealised_pnl,
+        };
+        self.profit_and_loss_history.push(new_pnl);
+
+        // a base asset fee on a sale is the same value as taking it from the proceeds
+        let (fee_asset, fee, _) = self.charge_fee(fee);
+        let cash_fee = match fee_asset {
+            FeeAsset::Bnb { .. } => 0.,
+            _ => fee,
+        };
+        self.position.quantity -= quantity;
+        self.available_fund += price * quantity - cash_fee;
+
+        self.trade_history.push(Trade {
+            timestamp,
+            buy_sell_indicator: BuySellIndicator::Sell,
+            quantity,
+            price,
+            fee,
+            fee_asset,
+            slippage,
+        });
+    }
+
+    pub fn reject(
+        &mut self,
+        timestamp: NaiveDateTime,
+        buy_sell_indicator: BuySellIndicator,
+        quantity: f64,
+        price: f64,
+        reason: RejectReason,
+    ) {
+        self.rejections.push(Rejection {
+            timestamp,
+            buy_sell_indicator,
+            quantity,
+            price,
+            reason,
+        });
+    }
+
+    /// credit interest on idle cash and staking rewards on the position since the last accrual
+    fn accrue_yield(&mut self, timestamp: NaiveDateTime) {
+        let years = (timestamp - self.last_accrual).num_seconds() as f64 / SECONDS_PER_YEAR;
+        if years <= 0. {
+            return;
+        }
+        let interest = self.available_fund.max(0.) * self.cash_yield.apr(self.last_accrual) * years;
+        self.available_fund += interest;
+        self.interest_earned += interest;
+
+        let reward = self.position.quantity.max(0.) * self.staking_yield.apr(self.last_accrual) * years;
+        if reward > 0. {
+            self.position.cost = self.average_cost(reward, 0.);
+            self.position.quantity += reward;
+            self.staking_rewards += reward;
+        }
+        self.last_accrual = timestamp;
+    }
+
+    pub fn mark_to_market(&mut self, timestamp: NaiveDateTime, closing_price: f64) {
+        self.accrue_yield(timestamp);
+        self.last_price = closing_price;
+        let last_pnl = self.profit_and_loss_history.last().unwrap();
+        let unrealised_pnl = self.position.quantity * (closing_price - self.position.cost);
+        let new_pnl = TimeValue {
+            timestamp,
+            unrealised_pnl,
+            realised_pnl: last_pnl.realised_pnl,
+        };
+        self.profit_and_loss_history.push(new_pnl);
+        self.equity_history.push(EquityPoint {
+            timestamp,
+            cash: self.available_fund,
+            quantity: self.position.quantity,
+            price: closing_price,
+            market_value: self.position.quantity * closing_price,
+            equity: self.equity(),
+            fees: self.fees_paid,
+            interest_earned: self.interest_earned,
+            staking_rewards: self.staking_rewards,
+            growth: self.time_weighted_growth(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_has_position() {
+        let initial_position = Position {
+            quantity: 123.1,
+            cost: 10.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let account = Account::new(1000.0, initial_position, start_timestamp);
+        assert_eq!(account.position.quantity, 123.1);
+    }
+
+    #[test]
+    fn test_open() {
+        let initial_position = Position {
+            quantity: 100.0,
+            cost: 10.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(5000.0, initial_position, start_timestamp);
+        let timestamp = NaiveDate::from_ymd(2021, 10, 31).and_hms(0, 0, 0);
+        account.open(timestamp, 100.0, 20.0, 0.02, 0.5);
+        assert_eq!(
+            account.position,
+            Position {
+                cost: 15.0,
+                quantity: 200.0
+            }
+        );
+        assert_eq!(2999.98, account.available_fund);
+        assert_eq!(
+            vec![Trade {
+                timestamp: NaiveDate::from_ymd(2021, 10, 31).and_hms(0, 0, 0),
+                buy_sell_indicator: BuySellIndicator::Buy,
+                quantity: 100.0,
+                price: 20.0,
+                fee: 0.02,
+                fee_asset: FeeAsset::Quote,
+                slippage: 0.5,
+            }],
+            account.trade_history
+        )
+    }
+
+    #[test]
+    fn test_close() {
+        let initial_position = Position {
+            quantity: 100.0,
+            cost: 10.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(1000.0, initial_position, start_timestamp);
+        let timestamp = NaiveDate::from_ymd(2021, 10, 31).and_hms(0, 0, 0);
+        account.close(timestamp, 50.0, 20.0, 0.02, 0.5);
+        assert_eq!(
+            account.position,
+            Position {
+                cost: 10.0,
+                quantity: 50.0,
+            }
+        );
+        assert_eq!(account.available_fund, 1999.98);
+        assert_eq!(
+            vec![Trade {
+                timestamp: NaiveDate::from_ymd(2021, 10, 31).and_hms(0, 0, 0),
+                buy_sell_indicator: BuySellIndicator::Sell,
+                quantity: 50.0,
+                price: 20.0,
+                fee: 0.02,
+                fee_asset: FeeAsset::Quote,
+                slippage: 0.5,
+            }],
+            account.trade_history
+        )
+    }
+
+    #[test]
+    fn test_reject() {
+        let initial_position = Position {
+            quantity: 0.0,
+            cost: 0.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(1000.0, initial_position, start_timestamp);
+        account.reject(start_timestamp, BuySellIndicator::Buy, 0.001, 20.0, RejectReason::MinNotional);
+        assert_eq!(account.available_fund, 1000.0);
+        assert!(account.trade_history.is_empty());
+        assert_eq!(
+            vec![Rejection {
+                timestamp: start_timestamp,
+                buy_sell_indicator: BuySellIndicator::Buy,
+                quantity: 0.001,
+                price: 20.0,
+                reason: RejectReason::MinNotional,
+            }],
+            account.rejections
+        )
+    }
+
+    #[test]
+    fn test_mark_to_market() {
+        let initial_position = Position {
+            quantity: 100.0,
+            cost: 10.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(5000.0, initial_position, start_timestamp);
+        let timestamp = NaiveDate::from_ymd(2021, 10, 31).and_hms(0, 0, 0);
+        account.mark_to_market(timestamp, 20.0);
+
+        let latest_pnl = account.profit_and_loss_history.last().unwrap();
+        assert_eq!(
+            *latest_pnl,
+            TimeValue {
+                timestamp,
+                realised_pnl: 0.,
+                unrealised_pnl: 1000.
+            }
+        )
+    }
+
+    #[test]
+    fn test_equity_curve() {
+        let initial_position = Position {
+            quantity: 100.0,
+            cost: 10.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(5000.0, initial_position, start_timestamp);
+        let timestamp = NaiveDate::from_ymd(2021, 10, 31).and_hms(0, 0, 0);
+        account.open(timestamp, 100.0, 20.0, 0.02, 0.);
+        account.mark_to_market(timestamp, 25.0);
+
+        assert_eq!(account.equity_curve().len(), 2);
+        assert_eq!(account.equity_curve()[0].equity, 6000.);
+        assert_eq!(
+            *account.equity_curve().last().unwrap(),
+            EquityPoint {
+                timestamp,
+                cash: 2999.98,
+                quantity: 200.,
+                price: 25.,
+                market_value: 5000.,
+                equity: 7999.98,
+                fees: 0.02,
+                interest_earned: 0.,
+                staking_rewards: 0.,
+                growth: 7999.98 / 6000.,
+            }
+        );
+        assert_eq!(account.profit_and_loss_history.last().unwrap().total_pnl(), 2000.);
+    }
+
+    #[test]
+    fn test_monthly_cash_flows() {
+        let from = NaiveDate::from_ymd(2021, 11, 15);
+        let to = NaiveDate::from_ymd(2022, 2, 1);
+        let flows = CashFlow::monthly(from, to, 100.0);
+        assert_eq!(
+            flows,
+            vec![
+                CashFlow {
+                    timestamp: NaiveDate::from_ymd(2021, 12, 1).and_hms(0, 0, 0),
+                    amount: 100.0
+                },
+                CashFlow {
+                    timestamp: NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0),
+                    amount: 100.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_weekly_and_daily_cash_flows() {
+        // a Wednesday
+        let from = NaiveDate::from_ymd(2021, 11, 17);
+        let to = NaiveDate::from_ymd(2021, 12, 6);
+        let weekly: Vec<NaiveDateTime> = CashFlow::every(Frequency::Weekly, from, to, 10.)
+            .iter()
+            .map(|flow| flow.timestamp)
+            .collect();
+        assert_eq!(
+            weekly,
+            vec![
+                NaiveDate::from_ymd(2021, 11, 22).and_hms(0, 0, 0),
+                NaiveDate::from_ymd(2021, 11, 29).and_hms(0, 0, 0),
+            ]
+        );
+        assert_eq!(CashFlow::every(Frequency::Daily, from, to, 10.).len(), 19);
+        assert_eq!(
+            Frequency::Weekly.period_start(NaiveDate::from_ymd(2021, 11, 21).and_hms(23, 0, 0)),
+            NaiveDate::from_ymd(2021, 11, 15)
+        );
+    }
+
+    #[test]
+    fn test_process_cash_flows() {
+        let initial_position = Position {
+            quantity: 0.0,
+            cost: 0.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(1000.0, initial_position, start_timestamp);
+        account.schedule_cash_flows(vec![
+            CashFlow {
+                timestamp: NaiveDate::from_ymd(2021, 10, 1).and_hms(0, 0, 0),
+                amount: -5000.0,
+            },
+            CashFlow {
+                timestamp: NaiveDate::from_ymd(2021, 9, 15).and_hms(0, 0, 0),
+                amount: 500.0,
+            },
+        ]);
+        account.process_cash_flows(NaiveDate::from_ymd(2021, 9, 20).and_hms(0, 0, 0));
+        assert_eq!(account.available_fund, 1500.0);
+        account.process_cash_flows(NaiveDate::from_ymd(2021, 10, 1).and_hms(0, 0, 0));
+        assert_eq!(account.available_fund, 0.0);
+        assert_eq!(account.net_contribution(), 0.0);
+        assert_eq!(account.profit_and_loss_history.len(), 1);
+    }
+
+    #[test]
+    fn test_time_and_money_weighted_return() {
+        let initial_position = Position {
+            quantity: 0.0,
+            cost: 0.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(1000.0, initial_position, start_timestamp);
+        account.open(start_timestamp, 100.0, 10.0, 0.0, 0.0);
+
+        // the price halves, then a deposit is invested and the price recovers
+        let mid_timestamp = NaiveDate::from_ymd(2021, 7, 2).and_hms(12, 0, 0);
+        account.mark_to_market(mid_timestamp, 5.0);
+        account.deposit(mid_timestamp, 1000.0);
+        account.open(mid_timestamp, 200.0, 5.0, 0.0, 0.0);
+        let end_timestamp = NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0);
+        account.mark_to_market(end_timestamp, 10.0);
+
+        assert_eq!(account.equity(), 3000.0);
+        assert!(account.time_weighted_return().abs() < 1e-12);
+        let growth = crate::metrics::growth_index(&account);
+        assert_eq!(growth[1].1, 0.5);
+        assert_eq!(growth.last().unwrap().1, 1. + account.time_weighted_return());
+        let money_weighted_return = account.money_weighted_return().unwrap();
+        assert!(money_weighted_return > 0.5);
+        assert!(money_weighted_return < 1.5);
+    }
+
+    #[test]
+    fn test_cash_yield() {
+        let initial_position = Position {
+            quantity: 0.0,
+            cost: 0.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(1000.0, initial_position, start_timestamp);
+        account.cash_yield = YieldModel::FlatApr(0.05);
+        let timestamp = start_timestamp + Duration::seconds(SECONDS_PER_YEAR as i64 / 2);
+        account.mark_to_market(timestamp, 10.0);
+        assert!((account.available_fund - 1025.0).abs() < 1e-9);
+        assert!((account.interest_earned - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_staking_yield() {
+        let initial_position = Position {
+            quantity: 100.0,
+            cost: 10.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(0.0, initial_position, start_timestamp);
+        account.staking_yield = YieldModel::FlatApr(0.04);
+        let timestamp = start_timestamp + Duration::seconds(SECONDS_PER_YEAR as i64);
+        account.mark_to_market(timestamp, 10.0);
+        assert!((account.position.quantity - 104.0).abs() < 1e-9);
+        assert!((account.staking_rewards - 4.0).abs() < 1e-9);
+        assert!((account.position.cost - 1000.0 / 104.0).abs() < 1e-9);
+        assert!((account.equity() - 1040.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_yield_time_series() {
+        let start_timestamp = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let change_timestamp = NaiveDate::from_ymd(2021, 6, 1).and_hms(0, 0, 0);
+        let model = YieldModel::TimeSeries(vec![(start_timestamp, 0.01), (change_timestamp, 0.03)]);
+        assert_eq!(model.apr(NaiveDate::from_ymd(2020, 12, 31).and_hms(0, 0, 0)), 0.0);
+        assert_eq!(model.apr(start_timestamp), 0.01);
+        assert_eq!(model.apr(NaiveDate::from_ymd(2021, 7, 1).and_hms(0, 0, 0)), 0.03);
+    }
+
+    #[test]
+    fn test_fee_in_base_asset() {
+        let initial_position = Position {
+            quantity: 0.0,
+            cost: 0.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(1000.0, initial_position, start_timestamp);
+        account.fee_asset = FeeAsset::Base;
+        account.open(start_timestamp, 10.0, 100.0, 10.0, 0.0);
+        assert_eq!(account.available_fund, 0.0);
+        assert_eq!(account.position.quantity, 9.9);
+        assert!((account.position.cost - 1000.0 / 9.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fee_in_bnb() {
+        let initial_position = Position {
+            quantity: 0.0,
+            cost: 0.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(1000.0, initial_position, start_timestamp);
+        account.pay_fees_in_bnb(0.1, 100.0, 0.25);
+        assert_eq!(account.net_contribution(), 1010.0);
+
+        account.open(start_timestamp, 5.0, 100.0, 8.0, 0.0);
+        assert_eq!(account.available_fund, 500.0);
+        assert!((account.bnb_balance - 0.04).abs() < 1e-12);
+
+        // not enough BNB left, so the fee falls back to the quote currency
+        account.close(start_timestamp, 5.0, 100.0, 8.0, 0.0);
+        assert_eq!(account.available_fund, 992.0);
+        assert!((account.bnb_balance - 0.04).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_rolling_volume() {
+        let initial_position = Position {
+            quantity: 0.0,
+            cost: 0.0,
+        };
+        let start_timestamp = NaiveDate::from_ymd(2021, 9, 1).and_hms(0, 0, 0);
+        let mut account = Account::new(10000.0, initial_position, start_timestamp);
+        account.open(start_timestamp, 10.0, 100.0, 0.0, 0.0);
+        account.close(start_timestamp + Duration::days(20), 10.0, 110.0, 0.0, 0.0);
+        let timestamp = start_timestamp + Duration::days(35);
+        assert_eq!(account.rolling_volume(timestamp, Duration::days(30)), 1100.0);
+        assert_eq!(account.rolling_volume(timestamp, Duration::days(40)), 2100.0);
+    }
+}
@@ -0,0 +1,236 @@
+use crate::account::Account;
+use crate::orders::OrderBook;
+use crate::traders::GenericTrader;
+use std::collections::VecDeque;
+use yata::core::Action;
+
+use log::{debug, info};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExecutionTiming {
+    /// fill at the close of the candle that generated the signal
+    SameBarClose,
+    /// fill at the open of the candle this many bars after the signal, 1 being the next bar
+    NextBarOpen(usize),
+}
+
+struct PendingAction {
+    bars_remaining: usize,
+    action: Action,
+}
+
+pub fn run_backtest<'a, T>(trader: &mut T, account: &mut Account, timing: ExecutionTiming)
+where
+    T: GenericTrader<'a>,
+{
+    info!("running backtest with {:?} execution", timing);
+    let mut pending: VecDeque<PendingAction> = VecDeque::new();
+    let mut orders = OrderBook::new();
+    while let Some((kline, action)) = trader.next_signal(account) {
+        account.process_cash_flows(kline.start_time);
+        for order in pending.iter_mut() {
+            order.bars_remaining -= 1;
+        }
+        while pending.front().is_some_and(|order| order.bars_remaining == 0) {
+            let order = pending.pop_front().unwrap();
+            trader.execute(order.action, kline.start_time, kline.open, &kline, account);
+        }
+
+        for expired in orders.expire(kline.start_time) {
+            debug!("order #{} expired", expired.id);
+        }
+        for fill in orders.match_kline(&kline) {
+            trader.execute_order(&fill, kline.end_time, &kline, account);
+        }
+
+        match timing {
+            ExecutionTiming::SameBarClose => {
+                trader.execute(action, kline.end_time, kline.close, &kline, account)
+            }
+            ExecutionTiming::NextBarOpen(delay) => {
+                if action != Action::None {
+                    pending.push_back(PendingAction {
+                        bars_remaining: delay.max(1),
+                        action,
+                    });
+                }
+            }
+        }
+        trader.manage_orders(&kline, account, &mut orders);
+        account.mark_to_market(kline.end_time, kline.close);
+    }
+    if !pending.is_empty() {
+        debug!("{} signals left unfilled at the end of the data", pending.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Position;
+    use crate::data::{BinanceKline, RejectReason, SymbolRules};
+    use crate::indicators::{BinanceIndicatorInstance, HODL};
+    use crate::orders::{OrderSide, OrderType};
+    use crate::traders::{HODLTrader, Slippage, StakeSize, TradingFee};
+    use chrono::{Duration, NaiveDate};
+    use yata::prelude::*;
+
+    struct LimitBuyer<'a> {
+        kline_feed: &'a mut dyn Iterator<Item = BinanceKline>,
+        indicator: Box<dyn BinanceIndicatorInstance>,
+        placed: bool,
+    }
+
+    impl<'a> GenericTrader<'a> for LimitBuyer<'a> {
+        fn determine_trade(_signals: &[Action]) -> Action {
+            Action::None
+        }
+        fn stake_size(&self) -> StakeSize {
+            StakeSize::FixPercentage(1.)
+        }
+        fn trading_fee(&self) -> &TradingFee {
+            &TradingFee::FixFee(0.)
+        }
+        fn slippage(&self) -> Slippage {
+            Slippage::Zero
+        }
+        fn symbol_rules(&self) -> Option<SymbolRules> {
+            None
+        }
+        fn kline(&mut self) -> &mut dyn Iterator<Item = BinanceKline> {
+            self.kline_feed
+        }
+        fn indicator(&mut self) -> &mut dyn BinanceIndicatorInstance {
+            self.indicator.as_mut()
+        }
+        fn manage_orders(&mut self, kline: &BinanceKline, _account: &Account, orders: &mut OrderBook) {
+            if !self.placed {
+                let order_type = OrderType::Limit(kline.close + 4.);
+                orders.submit(OrderSide::Buy, order_type, 2., kline.end_time, None);
+                self.placed = true;
+            }
+        }
+    }
+
+    fn klines() -> Vec<BinanceKline> {
+        let start = NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0);
+        (0..4)
+            .map(|i| {
+                let price = 100. + 10. * i as f64;
+                BinanceKline {
+                    start_time: start + Duration::hours(i),
+                    open: price,
+                    high: price + 8.,
+                    low: price - 2.,
+                    close: price + 5.,
+                    volume: 1000.,
+                    end_time: start + Duration::hours(i + 1) - Duration::seconds(1),
+                }
+            })
+            .collect()
+    }
+
+    fn run(timing: ExecutionTiming) -> Account {
+        run_with(timing, TradingFee::FixFee(0.), None)
+    }
+
+    fn run_with(timing: ExecutionTiming, trading_fee: TradingFee, symbol_rules: Option<SymbolRules>) -> Account {
+        let klines = klines();
+        let start = klines[0].start_time;
+        let mut klines_iter = klines.into_iter();
+        let mut trader = HODLTrader::new(&mut klines_iter, trading_fee, Slippage::Zero, symbol_rules);
+        let position = Position { quantity: 0., cost: 0. };
+        let mut account = Account::new(1000., position, start);
+        run_backtest(&mut trader, &mut account, timing);
+        account.audit().expect("account does not reconcile");
+        account
+    }
+
+    fn run_limit_buyer(capital: f64) -> Account {
+        let klines = klines();
+        let start = klines[0].start_time;
+        let mut klines_iter = klines.into_iter();
+        let hodl = HODL::default().init(&klines_iter.next().unwrap()).unwrap();
+        let mut trader = LimitBuyer {
+            kline_feed: &mut klines_iter,
+            indicator: Box::new(hodl),
+            placed: false,
+        };
+        let position = Position { quantity: 0., cost: 0. };
+        let mut account = Account::new(capital, position, start);
+        run_backtest(&mut trader, &mut account, ExecutionTiming::SameBarClose);
+        assert_eq!(account.audit(), Ok(()));
+        account
+    }
+
+    #[test]
+    fn test_resting_limit_order() {
+        let account = run_limit_buyer(1000.);
+        assert_eq!(account.position, Position { quantity: 2., cost: 119. });
+        assert_eq!(account.available_fund, 762.);
+        assert!(account.rejections.is_empty());
+    }
+
+    #[test]
+    fn test_limit_order_short_of_cash() {
+        let account = run_limit_buyer(119.);
+        assert_eq!(account.position, Position { quantity: 1., cost: 119. });
+        assert_eq!(account.available_fund, 0.);
+        assert_eq!(account.rejections.len(), 1);
+        let rejection = &account.rejections[0];
+        assert_eq!(rejection.reason(), RejectReason::InsufficientFunds);
+        assert_eq!(rejection.quantity(), 1.);
+        assert_eq!(rejection.price(), 119.);
+    }
+
+    #[test]
+    fn test_symbol_rules_rounding() {
+        let rules = SymbolRules {
+            tick_size: 0.01,
+            step_size: 1.,
+            min_quantity: 1.,
+            min_notional: 10.,
+        };
+        let account = run_with(ExecutionTiming::SameBarClose, TradingFee::FixFee(0.), Some(rules));
+        assert_eq!(account.position, Position { quantity: 8., cost: 115. });
+        assert_eq!(account.available_fund, 80.);
+        assert_eq!(account.rejections.len(), 2);
+    }
+
+    #[test]
+    fn test_symbol_rules_rejection() {
+        let rules = SymbolRules {
+            min_notional: 5000.,
+            ..SymbolRules::default()
+        };
+        let account = run_with(ExecutionTiming::SameBarClose, TradingFee::FixFee(0.), Some(rules));
+        assert_eq!(account.position.quantity, 0.);
+        assert_eq!(account.available_fund, 1000.);
+        assert_eq!(account.rejections.len(), 3);
+    }
+
+    #[test]
+    fn test_buy_spends_stake_including_fee() {
+        let account = run_with(ExecutionTiming::SameBarClose, TradingFee::PercentageFee(0.01), None);
+        assert!(account.available_fund.abs() < 1e-9);
+        assert!((account.position.quantity * 115. * 1.01 - 1000.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_same_bar_close() {
+        let account = run(ExecutionTiming::SameBarClose);
+        assert_eq!(account.position.cost, 115.);
+    }
+
+    #[test]
+    fn test_next_bar_open() {
+        let account = run(ExecutionTiming::NextBarOpen(1));
+        assert_eq!(account.position.cost, 120.);
+    }
+
+    #[test]
+    fn test_delayed_open() {
+        let account = run(ExecutionTiming::NextBarOpen(2));
+        assert_eq!(account.position.cost, 130.);
+    }
+}
@@ -0,0 +1,430 @@
+use crate::account::{Account, BuySellIndicator};
+use crate::config::StrategyConfig;
+use crate::data::BinanceKline;
+use crate::indicators::{macd_series, macd_warm_up};
+use crate::metrics::growth_index;
+use crate::traders::MacdPeriods;
+use chrono::NaiveDateTime;
+use clap::ValueEnum;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::register_font;
+use std::error::Error;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const SIZE: (u32, u32) = (1200, 800);
+/// candles drawn per chart, longer periods are merged into wider candles
+const MAX_CANDLES: usize = 240;
+const MAX_POINTS: usize = 1500;
+/// fonts tried in order when none is given, the first that exists is used
+const FONT_PATHS: [&str; 6] = [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+const PALETTE: [RGBColor; 6] = [
+    RGBColor(31, 119, 180),
+    RGBColor(255, 127, 14),
+    RGBColor(44, 160, 44),
+    RGBColor(214, 39, 40),
+    RGBColor(148, 103, 189),
+    RGBColor(23, 190, 207),
+];
+
+type ChartResult = Result<(), Box<dyn Error>>;
+type Series = Vec<(i64, f64)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChartFormat {
+    Svg,
+    Png,
+}
+
+impl ChartFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ChartFormat::Svg => "svg",
+            ChartFormat::Png => "png",
+        }
+    }
+}
+
+/// the font registered last, failed attempts leave it as it was
+static FONT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// register the font labels are drawn with, charts cannot be rendered before this succeeds once.
+/// without a path the registered font is kept, or the first of `FONT_PATHS` that exists is loaded
+pub fn load_font(path: Option<&Path>) -> Result<PathBuf, String> {
+    let mut font = FONT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(loaded) = font.as_ref().filter(|loaded| path.is_none_or(|path| path == loaded.as_path())) {
+        return Ok(loaded.clone());
+    }
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => FONT_PATHS
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| path.exists())
+            .ok_or_else(|| "no font found for the chart labels, pass one with --font".to_string())?,
+    };
+    let bytes = fs::read(&path).map_err(|e| format!("unable to read font [{}]: {}", path.display(), e))?;
+    // plotters keeps the font for the rest of the run
+    register_font("sans-serif", FontStyle::Normal, Box::leak(bytes.into_boxed_slice()))
+        .map_err(|_| format!("[{}] is not a usable font", path.display()))?;
+    *font = Some(path.clone());
+    Ok(path)
+}
+
+/// consecutive klines merged so that at most `MAX_CANDLES` are left
+fn merge_candles(klines: &[BinanceKline]) -> Vec<BinanceKline> {
+    let size = klines.len().div_ceil(MAX_CANDLES).max(1);
+    klines
+        .chunks(size)
+        .map(|chunk| {
+            let (first, last) = (chunk[0], chunk[chunk.len() - 1]);
+            BinanceKline {
+                start_time: first.start_time,
+                open: first.open,
+                high: chunk.iter().map(|kline| kline.high).fold(f64::NEG_INFINITY, f64::max),
+                low: chunk.iter().map(|kline| kline.low).fold(f64::INFINITY, f64::min),
+                close: last.close,
+                volume: chunk.iter().map(|kline| kline.volume).sum(),
+                end_time: last.end_time,
+            }
+        })
+        .collect()
+}
+
+fn thin(series: Series) -> Series {
+    let step = series.len().div_ceil(MAX_POINTS).max(1);
+    let last = series.last().copied();
+    let mut thinned: Series = series.into_iter().step_by(step).collect();
+    if last != thinned.last().copied() {
+        thinned.extend(last);
+    }
+    thinned
+}
+
+fn bounds<'a>(series: impl Iterator<Item = &'a (i64, f64)>) -> (i64, i64, f64, f64) {
+    let (mut start, mut end, mut low, mut high) = (i64::MAX, i64::MIN, f64::INFINITY, f64::NEG_INFINITY);
+    for (x, y) in series {
+        start = start.min(*x);
+        end = end.max(*x);
+        low = low.min(*y);
+        high = high.max(*y);
+    }
+    let pad = ((high - low) * 0.05).max(1e-9);
+    (start, end.max(start + 1), low - pad, high + pad)
+}
+
+fn date_label(timestamp: &i64) -> String {
+    NaiveDateTime::from_timestamp(*timestamp, 0).date().to_string()
+}
+
+/// candlesticks with the run's buys and sells on top, the MACD line, signal and histogram below for a run on `macd`
+fn draw_trades<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    title: &str,
+    klines: &[BinanceKline],
+    account: &Account,
+    macd: Option<MacdPeriods>,
+) -> ChartResult
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    let (upper, lower) = match macd {
+        Some(_) => {
+            let (upper, lower) = root.split_vertically(SIZE.1 * 2 / 3);
+            (upper, Some(lower))
+        }
+        None => (root.clone(), None),
+    };
+    let candles = merge_candles(klines);
+    let prices: Series = candles
+        .iter()
+        .flat_map(|kline| [(kline.start_time.timestamp(), kline.low), (kline.end_time.timestamp(), kline.high)])
+        .collect();
+    let (start, end, low, high) = bounds(prices.iter());
+    let mut chart = ChartBuilder::on(&upper)
+        .caption(title, ("sans-serif", 22))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(70)
+        .build_cartesian_2d(start..end, low..high)?;
+    chart
+        .configure_mesh()
+        .x_labels(8)
+        .x_label_formatter(&date_label)
+        .y_label_formatter(&|price| format!("{:.2}", price))
+        .draw()?;
+    let width = ((SIZE.0 - 100) as usize / candles.len().max(1)).saturating_sub(1).clamp(1, 12) as u32;
+    chart.draw_series(candles.iter().map(|kline| {
+        let middle = kline.start_time.timestamp() + (kline.end_time - kline.start_time).num_seconds() / 2;
+        CandleStick::new(
+            middle,
+            kline.open,
+            kline.high,
+            kline.low,
+            kline.close,
+            PALETTE[2].filled(),
+            PALETTE[3].filled(),
+            width,
+        )
+    }))?;
+    // an upward triangle under each buy, a downward one over each sale
+    chart.draw_series(account.trade_history.iter().map(|trade| {
+        let (tip, colour) = match trade.buy_sell_indicator() {
+            BuySellIndicator::Buy => (10, PALETTE[0]),
+            BuySellIndicator::Sell => (-10, PALETTE[1]),
+        };
+        EmptyElement::at((trade.timestamp().timestamp(), trade.price()))
+            + Polygon::new(vec![(0, 0), (-6, tip), (6, tip)], colour.filled())
+    }))?;
+    if let (Some(lower), Some(periods)) = (lower, macd) {
+        draw_macd(&lower, klines, periods, start..end)?;
+    }
+    root.present()?;
+    Ok(())
+}
+
+/// the MACD line, signal and histogram over `range`, leaving out the bars the indicator is still warming up on
+fn draw_macd<DB: DrawingBackend>(
+    area: &DrawingArea<DB, Shift>,
+    klines: &[BinanceKline],
+    periods: MacdPeriods,
+    range: Range<i64>,
+) -> ChartResult
+where
+    DB::ErrorType: 'static,
+{
+    // the series starts at the second kline, the warm-up counts from the first
+    let warm_up = macd_warm_up(periods).saturating_sub(1);
+    let macd: Vec<_> = macd_series(klines, periods).into_iter().skip(warm_up).collect();
+    let line = thin(macd.iter().map(|(timestamp, line, _)| (timestamp.timestamp(), *line)).collect());
+    let signal = thin(macd.iter().map(|(timestamp, _, signal)| (timestamp.timestamp(), *signal)).collect());
+    let histogram = thin(
+        macd.iter()
+            .map(|(timestamp, line, signal)| (timestamp.timestamp(), line - signal))
+            .collect(),
+    );
+    let (_, _, low, high) = bounds(line.iter().chain(signal.iter()).chain(histogram.iter()));
+    let mut chart = ChartBuilder::on(area)
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(70)
+        .build_cartesian_2d(range, low..high)?;
+    chart
+        .configure_mesh()
+        .x_labels(8)
+        .x_label_formatter(&date_label)
+        .y_label_formatter(&|value| format!("{:.2}", value))
+        .draw()?;
+    chart.draw_series(histogram.windows(2).map(|pair| {
+        let (x, value) = pair[0];
+        let colour = if value >= 0. { PALETTE[2] } else { PALETTE[3] };
+        Rectangle::new([(x, 0.), (pair[1].0, value)], colour.mix(0.4).filled())
+    }))?;
+    chart
+        .draw_series(LineSeries::new(line, PALETTE[0].stroke_width(2)))?
+        .label(format!("MACD {}", periods))
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], PALETTE[0]));
+    chart
+        .draw_series(LineSeries::new(signal, PALETTE[1].stroke_width(2)))?
+        .label("signal")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], PALETTE[1]));
+    chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()?;
+    Ok(())
+}
+
+/// equity of every run on top and its drawdown below, sharing the time axis
+fn draw_equity<DB: DrawingBackend>(root: DrawingArea<DB, Shift>, title: &str, runs: &[(String, &Account)]) -> ChartResult
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    let (upper, lower) = root.split_vertically(SIZE.1 / 2);
+    let equity: Vec<Series> = runs
+        .iter()
+        .map(|(_, account)| {
+            let curve = account.equity_curve().iter();
+            thin(curve.map(|point| (point.timestamp.timestamp(), point.equity)).collect())
+        })
+        .collect();
+    let drawdowns: Vec<Series> = runs
+        .iter()
+        .map(|(_, account)| {
+            let mut peak = f64::NEG_INFINITY;
+            let growth = growth_index(account).into_iter().map(|(timestamp, value)| {
+                peak = peak.max(value);
+                (timestamp.timestamp(), (value / peak - 1.) * 100.)
+            });
+            thin(growth.collect())
+        })
+        .collect();
+    let panels = [
+        (&upper, &equity, Some(title), "equity"),
+        (&lower, &drawdowns, None, "drawdown %"),
+    ];
+    for (area, series, caption, description) in panels {
+        let (start, end, low, high) = bounds(series.iter().flatten());
+        let mut builder = ChartBuilder::on(area);
+        if let Some(caption) = caption {
+            builder.caption(caption, ("sans-serif", 22));
+        }
+        let mut chart = builder
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(70)
+            .build_cartesian_2d(start..end, low..high)?;
+        chart
+            .configure_mesh()
+            .x_labels(8)
+            .x_label_formatter(&date_label)
+            .y_desc(description)
+            .y_label_formatter(&|value| format!("{:.0}", value))
+            .draw()?;
+        for ((name, _), (series, colour)) in runs.iter().zip(series.iter().zip(PALETTE.iter().cycle())) {
+            chart
+                .draw_series(LineSeries::new(series.iter().copied(), colour.stroke_width(2)))?
+                .label(name.as_str())
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], *colour));
+        }
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+    }
+    root.present()?;
+    Ok(())
+}
+
+/// price and trades of one run, and its MACD when it traded on one
+pub fn trades_chart(
+    path: &Path,
+    format: ChartFormat,
+    title: &str,
+    klines: &[BinanceKline],
+    account: &Account,
+    macd: Option<MacdPeriods>,
+) -> ChartResult {
+    match format {
+        ChartFormat::Svg => {
+            draw_trades(SVGBackend::new(path, SIZE).into_drawing_area(), title, klines, account, macd)
+        }
+        ChartFormat::Png => {
+            draw_trades(BitMapBackend::new(path, SIZE).into_drawing_area(), title, klines, account, macd)
+        }
+    }
+}
+
+/// equity and drawdown of several runs on one plot
+pub fn equity_chart(path: &Path, format: ChartFormat, title: &str, runs: &[(String, &Account)]) -> ChartResult {
+    match format {
+        ChartFormat::Svg => draw_equity(SVGBackend::new(path, SIZE).into_drawing_area(), title, runs),
+        ChartFormat::Png => draw_equity(BitMapBackend::new(path, SIZE).into_drawing_area(), title, runs),
+    }
+}
+
+fn file_name(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+/// the equity chart of every run on the symbol and a trades chart per run, in each format,
+/// returning the files written
+pub fn symbol_charts(
+    dir: &Path,
+    formats: &[ChartFormat],
+    symbol: &str,
+    klines: &[BinanceKline],
+    runs: &[(&StrategyConfig, &Account)],
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    let named: Vec<(String, &Account)> = runs.iter().map(|(strategy, account)| (strategy.name(), *account)).collect();
+    let mut written = Vec::new();
+    for format in formats {
+        let path = dir.join(format!("{}-equity.{}", file_name(symbol), format.extension()));
+        equity_chart(&path, *format, symbol, &named)?;
+        written.push(path);
+        for (strategy, account) in runs {
+            let name = strategy.name();
+            let path = dir.join(format!("{}-{}.{}", file_name(symbol), file_name(&name), format.extension()));
+            trades_chart(&path, *format, &format!("{} {}", symbol, name), klines, account, strategy.macd())?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines;
+    use crate::strategy::{run_strategy, Strategy, StrategySettings};
+    use chrono::NaiveDate;
+    use tempfile::tempdir;
+
+    fn klines() -> Vec<BinanceKline> {
+        test_klines(NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0), 24 * 30, 0.)
+    }
+
+    #[test]
+    fn test_merge_candles() {
+        let klines = klines();
+        let candles = merge_candles(&klines);
+        assert_eq!(candles.len(), MAX_CANDLES);
+        assert_eq!(candles[0].open, klines[0].open);
+        assert_eq!(candles[0].close, klines[2].close);
+        assert_eq!(candles[0].volume, 3000.);
+        assert_eq!(candles.last().unwrap().end_time, klines.last().unwrap().end_time);
+    }
+
+    #[test]
+    fn test_missing_font() {
+        let missing = Path::new("/nonexistent/font.ttf");
+        assert!(load_font(Some(missing)).unwrap_err().contains("/nonexistent/font.ttf"));
+        assert!(load_font(Some(missing)).is_err());
+    }
+
+    #[test]
+    #[ignore = "needs one of the system fonts in FONT_PATHS, run with --ignored"]
+    fn test_render() {
+        load_font(None).expect("no system font to render with");
+        let klines = klines();
+        let settings = StrategySettings::default();
+        let strategies = [Strategy::Macd, Strategy::Hodl].map(StrategyConfig::from_strategy);
+        let accounts: Vec<Account> = strategies
+            .iter()
+            .map(|strategy| run_strategy(strategy.strategy(), &klines, &strategy.settings(&settings)))
+            .collect();
+        let runs: Vec<(&StrategyConfig, &Account)> = strategies.iter().zip(accounts.iter()).collect();
+        let dir = tempdir().unwrap();
+        let written = symbol_charts(dir.path(), ChartFormat::value_variants(), "ETHUSDT", &klines, &runs).unwrap();
+        assert_eq!(written.len(), 6);
+        for path in written {
+            assert!(fs::metadata(&path).unwrap().len() > 0, "{} is empty", path.display());
+        }
+        let svg = fs::read_to_string(dir.path().join("ethusdt-macd.svg")).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("ETHUSDT MACD"));
+        assert!(svg.contains("MACD 12/26/9"));
+        // HODL does not trade on a MACD, so it gets no MACD pane
+        let svg = fs::read_to_string(dir.path().join("ethusdt-hodl.svg")).unwrap();
+        assert!(!svg.contains("signal"));
+    }
+}
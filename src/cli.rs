@@ -0,0 +1,486 @@
+use chrono::{Duration, NaiveDate, Utc};
+use clap::{Args, Parser, Subcommand};
+use crypto_strategy_analysis::account::Frequency;
+#[cfg(feature = "charts")]
+use crypto_strategy_analysis::charts::ChartFormat;
+use crypto_strategy_analysis::config::{
+    AccountConfig, ConfigError, DataConfig, DataSource, ExecutionConfig, Experiment, FeeConfig, Period,
+    SlippageConfig, StrategyConfig,
+};
+use crypto_strategy_analysis::export::ExportFormat;
+use crypto_strategy_analysis::grid::{GridConfig, MacdGrid};
+use crypto_strategy_analysis::report::RankBy;
+use crypto_strategy_analysis::runner::Outputs;
+use crypto_strategy_analysis::strategy::Strategy;
+use crypto_strategy_analysis::traders::MacdPeriods;
+use crypto_strategy_analysis::walk_forward::WalkForwardConfig;
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Backtest crypto trading strategies on Binance klines")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+    /// where downloaded klines are kept between runs
+    #[arg(long, global = true, default_value = "cache")]
+    pub cache_dir: PathBuf,
+    /// Binance exchangeInfo file with the symbols' lot size, tick size and minimum notional
+    #[arg(long, global = true, default_value = "exchangeInfo.json")]
+    pub exchange_info: PathBuf,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Download klines into the cache
+    Download(DataArgs),
+    /// Backtest strategies and log a summary of each run
+    Backtest(BacktestArgs),
+    /// Rank every strategy on every symbol in one table
+    Compare(BacktestArgs),
+    /// Log every metric of each run and compare it against HODL, or --benchmark's HODL
+    Report(BacktestArgs),
+    /// Scroll through the runs bar by bar in the terminal
+    #[cfg(feature = "tui")]
+    Explore(BacktestArgs),
+    /// Run every combination of strategy parameters and rank them by --rank-by
+    Grid(GridArgs),
+    /// Pick the best grid combination on rolling in-sample windows and trade it on the days after each
+    WalkForward(WalkForwardArgs),
+    /// Inspect or clear the kline cache
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheAction {
+    /// List the cached symbols and the period they cover
+    List,
+    /// Remove cached klines
+    Clear {
+        /// only remove this symbol
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct DataArgs {
+    /// comma separated, e.g. ETHUSDT,BTCUSDT
+    #[arg(long = "symbols", alias = "symbol", value_delimiter = ',', default_value = "ETHUSDT")]
+    pub symbols: Vec<String>,
+    /// kline interval as Binance names it, e.g. 1h, 4h, 1d
+    #[arg(long, default_value = "1h")]
+    pub interval: String,
+    /// first day to include
+    #[arg(long, default_value = "2020-01-01")]
+    pub from: NaiveDate,
+    /// first day to leave out, yesterday's close by default
+    #[arg(long)]
+    pub to: Option<NaiveDate>,
+}
+
+impl DataArgs {
+    pub fn to(&self) -> NaiveDate {
+        self.to.unwrap_or_else(|| (Utc::today() - Duration::days(1)).naive_utc())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct BacktestArgs {
+    /// TOML or YAML file describing the whole experiment, the other flags are ignored when given
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    #[command(flatten)]
+    pub data: DataArgs,
+    /// comma separated ranges inside --from and --to to compare side by side, e.g. 2021-01-01..2021-07-01
+    #[arg(long, value_delimiter = ',')]
+    pub periods: Vec<Period>,
+    /// comma separated strategies to run
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "macd,macd-trailing-stop,hodl,dca")]
+    pub strategies: Vec<Strategy>,
+    /// starting cash in the quote currency, DCA starts without any and only invests its deposits
+    #[arg(long, default_value_t = 1000.)]
+    pub capital: f64,
+    /// fee as a fraction of the traded notional
+    #[arg(long, default_value_t = 0.005)]
+    pub fee: f64,
+    /// fraction of the available fund each buy spends
+    #[arg(long, default_value_t = 1.)]
+    pub stake: f64,
+    /// DCA deposit per period, see --dca-frequency
+    #[arg(long, default_value_t = 100.)]
+    pub dca_amount: f64,
+    /// how often DCA deposits and buys
+    #[arg(long, value_enum, default_value = "monthly")]
+    pub dca_frequency: Frequency,
+    /// trailing stop for MACD+TS as a fraction below the highest high
+    #[arg(long, default_value_t = 0.08)]
+    pub trailing_stop: f64,
+    /// MACD fast, slow and signal periods in bars
+    #[arg(long, default_value = "12/26/9")]
+    pub macd: MacdPeriods,
+    /// bars between a signal and its fill at the open, 0 fills at the signal bar's close
+    #[arg(long, default_value_t = 1)]
+    pub delay: usize,
+    /// annual rate for Sharpe, Sortino and Omega
+    #[arg(long, default_value_t = 0.)]
+    pub risk_free_rate: f64,
+    /// symbol whose HODL run the report measures every strategy against, e.g. BTCUSDT, each symbol's own by default
+    #[arg(long)]
+    pub benchmark: Option<String>,
+    /// write every run's summary, trades and equity curve into this directory
+    #[arg(long)]
+    pub export: Option<PathBuf>,
+    /// write a self-contained html report with charts to this file
+    #[arg(long)]
+    pub html: Option<PathBuf>,
+    /// render equity, drawdown, price, trade and MACD charts into this directory
+    #[cfg(feature = "charts")]
+    #[arg(long)]
+    pub charts: Option<PathBuf>,
+    /// comma separated chart formats
+    #[cfg(feature = "charts")]
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "svg,png")]
+    pub chart_format: Vec<ChartFormat>,
+    /// TrueType font for the chart labels, a common system font is looked for when not given
+    #[cfg(feature = "charts")]
+    #[arg(long)]
+    pub font: Option<PathBuf>,
+    /// comma separated export formats
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "json,csv")]
+    pub export_format: Vec<ExportFormat>,
+    /// metric the comparison table is ranked by, and the one a grid search picks the best parameters on
+    #[arg(long, value_enum, default_value = "return")]
+    pub rank_by: RankBy,
+    /// threads the runs are spread over, one per core by default
+    #[arg(long)]
+    pub jobs: Option<usize>,
+}
+
+impl BacktestArgs {
+    /// the experiment from `--config`, or else the one the flags describe
+    pub fn experiment(&self) -> Result<Experiment, ConfigError> {
+        if let Some(config) = &self.config {
+            return Experiment::from_file(config);
+        }
+        let strategies = self
+            .strategies
+            .iter()
+            .map(|strategy| match StrategyConfig::from_strategy(*strategy) {
+                StrategyConfig::Macd { name, .. } => StrategyConfig::Macd {
+                    name,
+                    stake: self.stake,
+                    macd: self.macd,
+                },
+                StrategyConfig::MacdTrailingStop { name, .. } => StrategyConfig::MacdTrailingStop {
+                    name,
+                    stake: self.stake,
+                    trailing_stop: self.trailing_stop,
+                    macd: self.macd,
+                },
+                StrategyConfig::Dca { name, .. } => StrategyConfig::Dca {
+                    name,
+                    amount: self.dca_amount,
+                    frequency: self.dca_frequency,
+                },
+                hodl => hodl,
+            })
+            .collect();
+        let experiment = Experiment {
+            data: DataConfig {
+                source: DataSource::Binance,
+                symbols: self.data.symbols.clone(),
+                interval: self.data.interval.clone(),
+                from: self.data.from,
+                to: self.data.to(),
+                periods: self.periods.clone(),
+            },
+            account: AccountConfig { capital: self.capital },
+            execution: ExecutionConfig {
+                delay: self.delay,
+                fee: FeeConfig::Percentage { rate: self.fee },
+                slippage: SlippageConfig::Zero,
+            },
+            strategies,
+            grid: Vec::new(),
+            walk_forward: None,
+            risk_free_rate: self.risk_free_rate,
+            benchmark: self.benchmark.clone(),
+        };
+        experiment.validate()?;
+        Ok(experiment)
+    }
+
+    pub fn outputs(&self) -> Outputs {
+        Outputs {
+            export: self.export.clone(),
+            export_formats: self.export_format.clone(),
+            html: self.html.clone(),
+            #[cfg(feature = "charts")]
+            charts: self.charts.clone(),
+            #[cfg(feature = "charts")]
+            chart_formats: self.chart_format.clone(),
+            #[cfg(feature = "charts")]
+            font: self.font.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct GridArgs {
+    #[command(flatten)]
+    pub backtest: BacktestArgs,
+    /// comma separated MACD fast periods to try, --macd's when not given
+    #[arg(long, value_delimiter = ',')]
+    pub fast: Vec<u8>,
+    /// comma separated MACD slow periods to try, --macd's when not given
+    #[arg(long, value_delimiter = ',')]
+    pub slow: Vec<u8>,
+    /// comma separated MACD signal periods to try, --macd's when not given
+    #[arg(long, value_delimiter = ',')]
+    pub signal: Vec<u8>,
+    /// comma separated MACD stakes to try, --stake when not given
+    #[arg(long, value_delimiter = ',')]
+    pub stakes: Vec<f64>,
+    /// comma separated MACD+TS trailing stops to try, --trailing-stop when not given
+    #[arg(long, value_delimiter = ',')]
+    pub trailing_stops: Vec<f64>,
+    /// comma separated DCA deposits to try, --dca-amount when not given
+    #[arg(long, value_delimiter = ',')]
+    pub dca_amounts: Vec<f64>,
+    /// comma separated DCA frequencies to try, --dca-frequency when not given
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub dca_frequencies: Vec<Frequency>,
+}
+
+/// the values given, or the single default when none were
+fn or_default<T: Clone>(values: &[T], default: T) -> Vec<T> {
+    if values.is_empty() {
+        vec![default]
+    } else {
+        values.to_vec()
+    }
+}
+
+impl GridArgs {
+    /// the experiment from `--config`, which needs a grid, or else one grid per strategy from the flags
+    pub fn experiment(&self) -> Result<Experiment, ConfigError> {
+        let mut experiment = self.backtest.experiment()?;
+        if self.backtest.config.is_none() {
+            experiment.grid = self.backtest.strategies.iter().map(|strategy| self.grid(*strategy)).collect();
+            experiment.validate()?;
+        }
+        if experiment.grid.is_empty() {
+            return Err(ConfigError::Invalid(vec![
+                "grid must list at least one strategy to search".to_string()
+            ]));
+        }
+        Ok(experiment)
+    }
+
+    fn grid(&self, strategy: Strategy) -> GridConfig {
+        let args = &self.backtest;
+        let macd = MacdGrid {
+            fast: or_default(&self.fast, args.macd.fast),
+            slow: or_default(&self.slow, args.macd.slow),
+            signal: or_default(&self.signal, args.macd.signal),
+        };
+        let stake = or_default(&self.stakes, args.stake);
+        match strategy {
+            Strategy::Macd => GridConfig::Macd { macd, stake },
+            Strategy::MacdTrailingStop => GridConfig::MacdTrailingStop {
+                macd,
+                stake,
+                trailing_stop: or_default(&self.trailing_stops, args.trailing_stop),
+            },
+            Strategy::Hodl => GridConfig::Hodl,
+            Strategy::Dca => GridConfig::Dca {
+                amount: or_default(&self.dca_amounts, args.dca_amount),
+                frequency: or_default(&self.dca_frequencies, args.dca_frequency),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct WalkForwardArgs {
+    #[command(flatten)]
+    pub grid: GridArgs,
+    /// days each combination is optimised on
+    #[arg(long, default_value_t = 180)]
+    pub in_sample_days: i64,
+    /// days the chosen combination is then traded on, also how far each window moves on
+    #[arg(long, default_value_t = 60)]
+    pub out_of_sample_days: i64,
+}
+
+impl WalkForwardArgs {
+    /// the grid experiment, with the window lengths from `--config` when it has them and from the flags otherwise
+    pub fn experiment(&self) -> Result<Experiment, ConfigError> {
+        let mut experiment = self.grid.experiment()?;
+        if self.grid.backtest.config.is_none() || experiment.walk_forward.is_none() {
+            experiment.walk_forward = Some(WalkForwardConfig {
+                in_sample_days: self.in_sample_days,
+                out_of_sample_days: self.out_of_sample_days,
+            });
+            experiment.validate()?;
+        }
+        Ok(experiment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto_strategy_analysis::backtest::ExecutionTiming;
+    use crypto_strategy_analysis::traders::TradingFee;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_backtest_args() {
+        let cli = Cli::parse_from([
+            "crypto-strategy-analysis",
+            "compare",
+            "--symbols",
+            "ETHUSDT,BTCUSDT",
+            "--strategies",
+            "hodl,dca",
+            "--from",
+            "2021-01-01",
+            "--fee",
+            "0.001",
+            "--delay",
+            "0",
+            "--export",
+            "results",
+            "--export-format",
+            "csv",
+            "--periods",
+            "2021-01-01..2021-07-01,2021-07-01..2022-01-01",
+            "--rank-by",
+            "sharpe",
+            "--to",
+            "2022-01-01",
+            "--benchmark",
+            "BTCUSDT",
+        ]);
+        let args = match cli.command {
+            Command::Compare(args) => args,
+            command => panic!("unexpected {:?}", command),
+        };
+        assert_eq!(args.data.symbols, vec!["ETHUSDT", "BTCUSDT"]);
+        assert_eq!(args.strategies, vec![Strategy::Hodl, Strategy::Dca]);
+        assert_eq!(args.data.from, NaiveDate::from_ymd(2021, 1, 1));
+        assert_eq!(args.export, Some(PathBuf::from("results")));
+        assert_eq!(args.export_format, vec![ExportFormat::Csv]);
+        assert_eq!(args.rank_by, RankBy::Sharpe);
+        let experiment = args.experiment().unwrap();
+        assert_eq!(
+            experiment.strategies[1],
+            StrategyConfig::Dca {
+                name: None,
+                amount: 100.,
+                frequency: Frequency::Monthly
+            }
+        );
+        assert_eq!(experiment.data.periods().len(), 2);
+        assert_eq!(experiment.benchmark.as_deref(), Some("BTCUSDT"));
+        assert_eq!(experiment.data.periods()[1].to_string(), "2021-07-01..2022-01-01");
+        let settings = experiment.settings();
+        assert_eq!(settings.trading_fee, TradingFee::PercentageFee(0.001));
+        assert_eq!(settings.timing, ExecutionTiming::SameBarClose);
+        assert_eq!(settings.capital, 1000.);
+    }
+
+    #[test]
+    fn test_grid_args() {
+        let cli = Cli::parse_from([
+            "crypto-strategy-analysis",
+            "grid",
+            "--strategies",
+            "macd,dca",
+            "--fast",
+            "8,12",
+            "--slow",
+            "21,26",
+            "--stakes",
+            "0.5,1",
+            "--dca-frequencies",
+            "weekly,monthly",
+            "--rank-by",
+            "sortino",
+        ]);
+        let args = match cli.command {
+            Command::Grid(args) => args,
+            command => panic!("unexpected {:?}", command),
+        };
+        let experiment = args.experiment().unwrap();
+        assert_eq!(experiment.grid.len(), 2);
+        assert_eq!(experiment.grid[0].strategies().len(), 2 * 2 * 2);
+        assert_eq!(experiment.grid[0].strategies()[0].name(), "MACD 8/21/9 50%");
+        assert_eq!(experiment.grid[1].strategies().len(), 2);
+        assert_eq!(args.backtest.rank_by, RankBy::Sortino);
+    }
+
+    #[test]
+    fn test_walk_forward_args() {
+        let cli = Cli::parse_from([
+            "crypto-strategy-analysis",
+            "walk-forward",
+            "--strategies",
+            "macd",
+            "--fast",
+            "8,12",
+            "--from",
+            "2021-01-01",
+            "--to",
+            "2021-07-01",
+            "--in-sample-days",
+            "90",
+        ]);
+        let args = match cli.command {
+            Command::WalkForward(args) => args,
+            command => panic!("unexpected {:?}", command),
+        };
+        let experiment = args.experiment().unwrap();
+        assert_eq!(experiment.grid[0].strategies().len(), 2);
+        assert_eq!(
+            experiment.walk_forward,
+            Some(WalkForwardConfig {
+                in_sample_days: 90,
+                out_of_sample_days: 60
+            })
+        );
+
+        let cli = Cli::parse_from([
+            "crypto-strategy-analysis",
+            "walk-forward",
+            "--from",
+            "2021-01-01",
+            "--to",
+            "2021-03-01",
+        ]);
+        let args = match cli.command {
+            Command::WalkForward(args) => args,
+            command => panic!("unexpected {:?}", command),
+        };
+        assert!(matches!(args.experiment(), Err(ConfigError::Invalid(problems)) if problems.len() == 1));
+    }
+
+    #[test]
+    fn test_invalid_flags() {
+        let cli = Cli::parse_from(["crypto-strategy-analysis", "backtest", "--stake", "2"]);
+        let args = match cli.command {
+            Command::Backtest(args) => args,
+            command => panic!("unexpected {:?}", command),
+        };
+        assert!(matches!(args.experiment(), Err(ConfigError::Invalid(problems)) if problems.len() == 2));
+    }
+}
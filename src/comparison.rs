@@ -0,0 +1,137 @@
+use crate::account::Account;
+use crate::config::{Period, StrategyConfig};
+use crate::data::BinanceKline;
+use crate::strategy::{run_strategy, StrategySettings};
+use rayon::prelude::*;
+
+/// one symbol over one period, with the settings every strategy on it starts from
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub symbol: String,
+    pub period: Period,
+    pub klines: Vec<BinanceKline>,
+    pub settings: StrategySettings,
+}
+
+impl Dataset {
+    /// keeps the klines opening inside `period`
+    pub fn new(symbol: &str, period: Period, klines: &[BinanceKline], settings: StrategySettings) -> Self {
+        let from = period.from.and_hms(0, 0, 0);
+        let to = period.to.and_hms(0, 0, 0);
+        Self {
+            symbol: symbol.to_string(),
+            period,
+            klines: klines
+                .iter()
+                .filter(|kline| kline.start_time >= from && kline.start_time < to)
+                .copied()
+                .collect(),
+            settings,
+        }
+    }
+}
+
+/// every strategy on every dataset, spread over the rayon pool one run at a time, each run's account handed to
+/// `keep` for what is kept of it. returns each dataset's results in strategy order, empty for a dataset without klines
+pub fn run_cells<T, F>(datasets: &[Dataset], strategies: &[StrategyConfig], keep: F) -> Vec<Vec<T>>
+where
+    T: Send,
+    F: Fn(&Dataset, &StrategyConfig, Account) -> T + Sync,
+{
+    let cells: Vec<(usize, &StrategyConfig)> = datasets
+        .iter()
+        .enumerate()
+        .filter(|(_, dataset)| !dataset.klines.is_empty())
+        .flat_map(|(i, _)| strategies.iter().map(move |strategy| (i, strategy)))
+        .collect();
+    let kept: Vec<(usize, T)> = cells
+        .into_par_iter()
+        .map(|(i, strategy)| {
+            let dataset = &datasets[i];
+            let account = run_strategy(strategy.strategy(), &dataset.klines, &strategy.settings(&dataset.settings));
+            (i, keep(dataset, strategy, account))
+        })
+        .collect();
+    let mut results: Vec<Vec<T>> = datasets.iter().map(|_| Vec::new()).collect();
+    for (i, result) in kept {
+        results[i].push(result);
+    }
+    results
+}
+
+/// every strategy's named run on every dataset
+pub fn run_matrix(datasets: &[Dataset], strategies: &[StrategyConfig]) -> Vec<Vec<(String, Account)>> {
+    run_cells(datasets, strategies, |_, strategy, account| (strategy.name(), account))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines;
+    use crate::account::Frequency;
+    use crate::traders::MacdPeriods;
+    use chrono::NaiveDate;
+
+    fn klines() -> Vec<BinanceKline> {
+        test_klines(NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0), 24 * 60, 0.)
+    }
+
+    fn period(from: (i32, u32, u32), to: (i32, u32, u32)) -> Period {
+        Period {
+            from: NaiveDate::from_ymd(from.0, from.1, from.2),
+            to: NaiveDate::from_ymd(to.0, to.1, to.2),
+        }
+    }
+
+    #[test]
+    fn test_dataset() {
+        let dataset = Dataset::new(
+            "ETHUSDT",
+            period((2021, 1, 10), (2021, 1, 20)),
+            &klines(),
+            StrategySettings::default(),
+        );
+        assert_eq!(dataset.klines.len(), 24 * 10);
+        assert_eq!(dataset.klines[0].start_time, NaiveDate::from_ymd(2021, 1, 10).and_hms(0, 0, 0));
+        assert_eq!(dataset.klines.last().unwrap().start_time, NaiveDate::from_ymd(2021, 1, 19).and_hms(23, 0, 0));
+    }
+
+    #[test]
+    fn test_run_matrix() {
+        let klines = klines();
+        let settings = StrategySettings::default();
+        let datasets = vec![
+            Dataset::new("ETHUSDT", period((2021, 1, 1), (2021, 2, 1)), &klines, settings.clone()),
+            Dataset::new("ETHUSDT", period((2022, 1, 1), (2022, 2, 1)), &klines, settings.clone()),
+            Dataset::new("ETHUSDT", period((2021, 2, 1), (2021, 3, 1)), &klines, settings),
+        ];
+        let strategies = vec![
+            StrategyConfig::Hodl { name: None },
+            StrategyConfig::Macd {
+                name: Some("MACD 50%".to_string()),
+                stake: 0.5,
+                macd: MacdPeriods::default(),
+            },
+            StrategyConfig::Dca {
+                name: None,
+                amount: 50.,
+                frequency: Frequency::Weekly,
+            },
+        ];
+        let runs = run_matrix(&datasets, &strategies);
+        assert_eq!(runs.len(), 3);
+        assert!(runs[1].is_empty());
+        for (dataset, runs) in [(&datasets[0], &runs[0]), (&datasets[2], &runs[2])] {
+            let names: Vec<&str> = runs.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, vec!["HODL", "MACD 50%", "DCA"]);
+            for (strategy, (_, account)) in strategies.iter().zip(runs.iter()) {
+                let sequential = run_strategy(
+                    strategy.strategy(),
+                    &dataset.klines,
+                    &strategy.settings(&dataset.settings),
+                );
+                assert_eq!(account.equity_curve(), sequential.equity_curve());
+            }
+        }
+    }
+}
@@ -0,0 +1,837 @@
+use crate::account::Frequency;
+use crate::backtest::ExecutionTiming;
+use crate::grid::GridConfig;
+use crate::strategy::{Strategy, StrategySettings};
+use crate::traders::{FeeTier, MacdPeriods, Slippage, StakeSize, TradingFee};
+use crate::walk_forward::WalkForwardConfig;
+use chrono::NaiveDate;
+use serde::de::{self, Deserializer};
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// a whole backtest described in one file, so re-running the file repeats the experiment
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Experiment {
+    pub data: DataConfig,
+    #[serde(default)]
+    pub account: AccountConfig,
+    #[serde(default)]
+    pub execution: ExecutionConfig,
+    #[serde(default)]
+    pub strategies: Vec<StrategyConfig>,
+    /// parameters to sweep with the grid command
+    #[serde(default)]
+    pub grid: Vec<GridConfig>,
+    /// window lengths for the walk-forward command
+    #[serde(default)]
+    pub walk_forward: Option<WalkForwardConfig>,
+    /// annual rate for Sharpe, Sortino and Omega
+    #[serde(default)]
+    pub risk_free_rate: f64,
+    /// symbol whose HODL run the report measures every strategy against, each symbol's own HODL when not given
+    #[serde(default)]
+    pub benchmark: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSource {
+    #[default]
+    Binance,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DataConfig {
+    #[serde(default)]
+    pub source: DataSource,
+    pub symbols: Vec<String>,
+    #[serde(default = "default_interval")]
+    pub interval: String,
+    /// first day to include
+    #[serde(deserialize_with = "date")]
+    pub from: NaiveDate,
+    /// first day to leave out, required so the experiment does not move with the calendar
+    #[serde(deserialize_with = "date")]
+    pub to: NaiveDate,
+    /// ranges inside `from` to `to` compared side by side, the whole range when empty
+    #[serde(default)]
+    pub periods: Vec<Period>,
+}
+
+impl DataConfig {
+    /// the ranges the strategies are run over
+    pub fn periods(&self) -> Vec<Period> {
+        if self.periods.is_empty() {
+            vec![Period {
+                from: self.from,
+                to: self.to,
+            }]
+        } else {
+            self.periods.clone()
+        }
+    }
+}
+
+/// a range of days, `to` left out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Period {
+    #[serde(deserialize_with = "date")]
+    pub from: NaiveDate,
+    #[serde(deserialize_with = "date")]
+    pub to: NaiveDate,
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.from, self.to)
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    /// `2021-01-01..2021-07-01`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = s
+            .split_once("..")
+            .ok_or_else(|| format!("expected a period like 2021-01-01..2021-07-01, got [{}]", s))?;
+        let parse = |date: &str| {
+            NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("expected a date like 2021-01-01, got [{}]", date))
+        };
+        Ok(Period {
+            from: parse(from)?,
+            to: parse(to)?,
+        })
+    }
+}
+
+/// a date either as a string or as a TOML local date
+fn date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Date {
+        Text(String),
+        Toml(toml::value::Datetime),
+    }
+    let text = match Date::deserialize(deserializer)? {
+        Date::Text(text) => text,
+        Date::Toml(datetime) => datetime.to_string(),
+    };
+    NaiveDate::parse_from_str(&text, "%Y-%m-%d")
+        .map_err(|_| de::Error::custom(format!("expected a date like 2021-01-01, got [{}]", text)))
+}
+
+fn default_interval() -> String {
+    "1h".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+    pub capital: f64,
+}
+
+impl Default for AccountConfig {
+    fn default() -> Self {
+        Self { capital: 1000. }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExecutionConfig {
+    /// bars between a signal and its fill at the open, 0 fills at the signal bar's close
+    #[serde(default = "default_delay")]
+    pub delay: usize,
+    #[serde(default)]
+    pub fee: FeeConfig,
+    #[serde(default)]
+    pub slippage: SlippageConfig,
+}
+
+fn default_delay() -> usize {
+    1
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            delay: default_delay(),
+            fee: FeeConfig::default(),
+            slippage: SlippageConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FeeConfig {
+    Percentage { rate: f64 },
+    Fixed { amount: f64 },
+    MakerTaker { maker: f64, taker: f64 },
+    Tiered { tiers: Vec<TierConfig> },
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        FeeConfig::Percentage { rate: 0.005 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TierConfig {
+    pub min_volume: f64,
+    pub maker: f64,
+    pub taker: f64,
+}
+
+impl FeeConfig {
+    pub fn trading_fee(&self) -> TradingFee {
+        match self {
+            FeeConfig::Percentage { rate } => TradingFee::PercentageFee(*rate),
+            FeeConfig::Fixed { amount } => TradingFee::FixFee(*amount),
+            FeeConfig::MakerTaker { maker, taker } => TradingFee::MakerTaker {
+                maker: *maker,
+                taker: *taker,
+            },
+            FeeConfig::Tiered { tiers } => TradingFee::Tiered(
+                tiers
+                    .iter()
+                    .map(|tier| FeeTier {
+                        min_volume: tier.min_volume,
+                        maker: tier.maker,
+                        taker: tier.taker,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    fn rates(&self) -> Vec<f64> {
+        match self {
+            FeeConfig::Percentage { rate } => vec![*rate],
+            FeeConfig::Fixed { .. } => vec![],
+            FeeConfig::MakerTaker { maker, taker } => vec![*maker, *taker],
+            FeeConfig::Tiered { tiers } => tiers.iter().flat_map(|tier| [tier.maker, tier.taker]).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SlippageConfig {
+    #[default]
+    Zero,
+    FixBps {
+        bps: f64,
+    },
+    VolatilityScaled {
+        factor: f64,
+    },
+    VolumeParticipation {
+        impact: f64,
+    },
+}
+
+impl SlippageConfig {
+    pub fn slippage(&self) -> Slippage {
+        match self {
+            SlippageConfig::Zero => Slippage::Zero,
+            SlippageConfig::FixBps { bps } => Slippage::FixBps(*bps),
+            SlippageConfig::VolatilityScaled { factor } => Slippage::VolatilityScaled(*factor),
+            SlippageConfig::VolumeParticipation { impact } => Slippage::VolumeParticipation(*impact),
+        }
+    }
+
+    fn parameter(&self) -> f64 {
+        match self {
+            SlippageConfig::Zero => 0.,
+            SlippageConfig::FixBps { bps } => *bps,
+            SlippageConfig::VolatilityScaled { factor } => *factor,
+            SlippageConfig::VolumeParticipation { impact } => *impact,
+        }
+    }
+}
+
+pub(crate) fn default_stake() -> f64 {
+    1.
+}
+
+pub(crate) fn default_trailing_stop() -> f64 {
+    0.08
+}
+
+pub(crate) fn default_dca_amount() -> f64 {
+    100.
+}
+
+/// a strategy and its parameters, `name` tells apart several runs of the same strategy
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum StrategyConfig {
+    Macd {
+        name: Option<String>,
+        /// fraction of the available fund each buy spends
+        #[serde(default = "default_stake")]
+        stake: f64,
+        #[serde(default)]
+        macd: MacdPeriods,
+    },
+    MacdTrailingStop {
+        name: Option<String>,
+        #[serde(default = "default_stake")]
+        stake: f64,
+        #[serde(default = "default_trailing_stop")]
+        trailing_stop: f64,
+        #[serde(default)]
+        macd: MacdPeriods,
+    },
+    Hodl {
+        name: Option<String>,
+    },
+    Dca {
+        name: Option<String>,
+        /// deposited and bought at the start of every period
+        #[serde(default = "default_dca_amount")]
+        amount: f64,
+        #[serde(default)]
+        frequency: Frequency,
+    },
+}
+
+impl StrategyConfig {
+    /// the strategy with every parameter at its default
+    pub fn from_strategy(strategy: Strategy) -> StrategyConfig {
+        match strategy {
+            Strategy::Macd => StrategyConfig::Macd {
+                name: None,
+                stake: default_stake(),
+                macd: MacdPeriods::default(),
+            },
+            Strategy::MacdTrailingStop => StrategyConfig::MacdTrailingStop {
+                name: None,
+                stake: default_stake(),
+                trailing_stop: default_trailing_stop(),
+                macd: MacdPeriods::default(),
+            },
+            Strategy::Hodl => StrategyConfig::Hodl { name: None },
+            Strategy::Dca => StrategyConfig::Dca {
+                name: None,
+                amount: default_dca_amount(),
+                frequency: Frequency::default(),
+            },
+        }
+    }
+
+    pub fn strategy(&self) -> Strategy {
+        match self {
+            StrategyConfig::Macd { .. } => Strategy::Macd,
+            StrategyConfig::MacdTrailingStop { .. } => Strategy::MacdTrailingStop,
+            StrategyConfig::Hodl { .. } => Strategy::Hodl,
+            StrategyConfig::Dca { .. } => Strategy::Dca,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        let name = match self {
+            StrategyConfig::Macd { name, .. }
+            | StrategyConfig::MacdTrailingStop { name, .. }
+            | StrategyConfig::Hodl { name }
+            | StrategyConfig::Dca { name, .. } => name,
+        };
+        name.clone().unwrap_or_else(|| self.strategy().name().to_string())
+    }
+
+    /// the MACD periods the strategy trades on, none for the strategies without a MACD
+    pub fn macd(&self) -> Option<MacdPeriods> {
+        match self {
+            StrategyConfig::Macd { macd, .. } | StrategyConfig::MacdTrailingStop { macd, .. } => Some(*macd),
+            StrategyConfig::Hodl { .. } | StrategyConfig::Dca { .. } => None,
+        }
+    }
+
+    /// the experiment's settings with this strategy's parameters applied
+    pub fn settings(&self, base: &StrategySettings) -> StrategySettings {
+        let mut settings = base.clone();
+        match self {
+            StrategyConfig::Macd { stake, macd, .. } => {
+                settings.stake_size = StakeSize::FixPercentage(*stake);
+                settings.macd = *macd;
+            }
+            StrategyConfig::MacdTrailingStop {
+                stake,
+                trailing_stop,
+                macd,
+                ..
+            } => {
+                settings.stake_size = StakeSize::FixPercentage(*stake);
+                settings.trailing_stop = *trailing_stop;
+                settings.macd = *macd;
+            }
+            StrategyConfig::Hodl { .. } => (),
+            StrategyConfig::Dca { amount, frequency, .. } => {
+                settings.dca_amount = *amount;
+                settings.dca_frequency = *frequency;
+            }
+        }
+        settings
+    }
+
+    fn validate(&self, field: &str, problems: &mut Vec<String>) {
+        match self {
+            StrategyConfig::Macd { stake, .. } | StrategyConfig::MacdTrailingStop { stake, .. }
+                if !(*stake > 0. && *stake <= 1.) =>
+            {
+                problems.push(format!("{}.stake must be above 0 and at most 1, got {}", field, stake))
+            }
+            _ => (),
+        }
+        match self {
+            StrategyConfig::Macd { macd, .. } | StrategyConfig::MacdTrailingStop { macd, .. } if !macd.is_valid() => {
+                problems.push(format!(
+                    "{}.macd periods {}/{}/{} need a fast period above 1 and below the slow one, and a signal above 1",
+                    field, macd.fast, macd.slow, macd.signal
+                ))
+            }
+            _ => (),
+        }
+        match self {
+            StrategyConfig::MacdTrailingStop { trailing_stop, .. } if !(*trailing_stop > 0. && *trailing_stop < 1.) => {
+                problems.push(format!(
+                    "{}.trailing_stop must be between 0 and 1, got {}",
+                    field, trailing_stop
+                ))
+            }
+            StrategyConfig::Dca { amount, .. } if !non_negative(*amount) => {
+                problems.push(format!("{}.amount must not be negative, got {}", field, amount))
+            }
+            _ => (),
+        }
+    }
+}
+
+fn is_symbol(symbol: &str) -> bool {
+    !symbol.is_empty() && symbol.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// false for NaN as well, so a NaN never passes validation
+fn positive(value: f64) -> bool {
+    value > 0.
+}
+
+fn non_negative(value: f64) -> bool {
+    value >= 0.
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    /// the file is not valid TOML or YAML, or does not match the schema
+    Parse(PathBuf, String),
+    UnknownFormat(PathBuf),
+    /// every problem found, each naming the offending field
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "unable to read [{}]: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "invalid config [{}]: {}", path.display(), e),
+            ConfigError::UnknownFormat(path) => write!(
+                f,
+                "unknown config format for [{}], expected a .toml, .yaml or .yml file",
+                path.display()
+            ),
+            ConfigError::Invalid(problems) => {
+                write!(f, "invalid config:\n  {}", problems.join("\n  "))
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl Experiment {
+    /// read and validate a TOML or YAML file, picking the format from the extension
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Experiment, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        let experiment: Experiment = match extension {
+            Some("toml") => {
+                toml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e.to_string()))?
+            }
+            Some("yaml") | Some("yml") => {
+                serde_yaml::from_str(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e.to_string()))?
+            }
+            _ => return Err(ConfigError::UnknownFormat(path.to_path_buf())),
+        };
+        experiment.validate()?;
+        Ok(experiment)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        if self.data.symbols.is_empty() {
+            problems.push("data.symbols must list at least one symbol".to_string());
+        }
+        for (i, symbol) in self.data.symbols.iter().enumerate() {
+            if !is_symbol(symbol) {
+                problems.push(format!("data.symbols[{}] [{}] is not a symbol like ETHUSDT", i, symbol));
+            }
+        }
+        if let Some(benchmark) = self.benchmark.as_ref().filter(|benchmark| !is_symbol(benchmark)) {
+            problems.push(format!("benchmark [{}] is not a symbol like BTCUSDT", benchmark));
+        }
+        const INTERVALS: [&str; 13] = [
+            "1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "1w",
+        ];
+        if !INTERVALS.contains(&self.data.interval.as_str()) {
+            problems.push(format!(
+                "data.interval [{}] must be one of {}",
+                self.data.interval,
+                INTERVALS.join(", ")
+            ));
+        }
+        if self.data.from >= self.data.to {
+            problems.push(format!(
+                "data.from ({}) must be before data.to ({})",
+                self.data.from, self.data.to
+            ));
+        }
+        for (i, period) in self.data.periods.iter().enumerate() {
+            if period.from >= period.to {
+                problems.push(format!("data.periods[{}] {} must end after it starts", i, period));
+            } else if period.from < self.data.from || period.to > self.data.to {
+                problems.push(format!(
+                    "data.periods[{}] {} must lie within data.from ({}) and data.to ({})",
+                    i, period, self.data.from, self.data.to
+                ));
+            }
+        }
+        if !positive(self.account.capital) {
+            problems.push(format!(
+                "account.capital must be positive, got {}",
+                self.account.capital
+            ));
+        }
+        if self
+            .execution
+            .fee
+            .rates()
+            .iter()
+            .any(|rate| !(*rate >= 0. && *rate < 1.))
+        {
+            problems.push("execution.fee rates must be at least 0 and below 1".to_string());
+        }
+        if let FeeConfig::Fixed { amount } = self.execution.fee {
+            if !non_negative(amount) {
+                problems.push(format!("execution.fee.amount must not be negative, got {}", amount));
+            }
+        }
+        if !non_negative(self.execution.slippage.parameter()) {
+            problems.push("execution.slippage must not be negative".to_string());
+        }
+        if self.strategies.is_empty() && self.grid.is_empty() {
+            problems.push("strategies or grid must list at least one strategy".to_string());
+        }
+        for (i, strategy) in self.strategies.iter().enumerate() {
+            strategy.validate(&format!("strategies[{}]", i), &mut problems);
+        }
+        for (i, grid) in self.grid.iter().enumerate() {
+            let field = format!("grid[{}]", i);
+            let strategies = grid.strategies();
+            if strategies.is_empty() {
+                problems.push(format!(
+                    "{} has nothing to run, every list needs a value and a fast period below a slow one",
+                    field
+                ));
+            }
+            // every combination repeats the same bad value, report it once
+            let mut grid_problems = Vec::new();
+            for strategy in strategies.iter() {
+                strategy.validate(&field, &mut grid_problems);
+            }
+            for problem in grid_problems {
+                if !problems.contains(&problem) {
+                    problems.push(problem);
+                }
+            }
+        }
+        if let Some(walk_forward) = &self.walk_forward {
+            if walk_forward.in_sample_days <= 0 || walk_forward.out_of_sample_days <= 0 {
+                problems.push(format!(
+                    "walk_forward needs positive in_sample_days and out_of_sample_days, got {} and {}",
+                    walk_forward.in_sample_days, walk_forward.out_of_sample_days
+                ));
+            } else {
+                for period in self.data.periods().iter().filter(|period| period.from < period.to) {
+                    if walk_forward.windows(*period).is_empty() {
+                        problems.push(format!(
+                            "walk_forward.in_sample_days ({}) leaves nothing out of sample in {}",
+                            walk_forward.in_sample_days, period
+                        ));
+                    }
+                }
+            }
+        }
+        let mut names: Vec<String> = self.strategies.iter().map(|strategy| strategy.name()).collect();
+        names.sort();
+        for pair in names.windows(2).filter(|pair| pair[0] == pair[1]) {
+            problems.push(format!(
+                "strategy name [{}] is used more than once, give each run a name",
+                pair[0]
+            ));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// settings shared by every strategy, before their own parameters are applied
+    pub fn settings(&self) -> StrategySettings {
+        StrategySettings {
+            capital: self.account.capital,
+            trading_fee: self.execution.fee.trading_fee(),
+            slippage: self.execution.slippage.slippage(),
+            timing: match self.execution.delay {
+                0 => ExecutionTiming::SameBarClose,
+                delay => ExecutionTiming::NextBarOpen(delay),
+            },
+            ..StrategySettings::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::Builder;
+
+    const TOML: &str = r#"
+        risk_free_rate = 0.02
+        benchmark = "BTCUSDT"
+
+        [data]
+        symbols = ["ETHUSDT", "BTCUSDT"]
+        interval = "4h"
+        from = "2021-01-01"
+        to = "2022-01-01"
+
+        [account]
+        capital = 5000.0
+
+        [execution]
+        delay = 0
+        fee = { model = "maker_taker", maker = 0.0002, taker = 0.0004 }
+        slippage = { model = "fix_bps", bps = 5.0 }
+
+        [[strategies]]
+        type = "macd-trailing-stop"
+        trailing_stop = 0.1
+
+        [[strategies]]
+        type = "dca"
+        name = "DCA 200"
+        amount = 200.0
+    "#;
+
+    const YAML: &str = r#"
+data:
+  symbols: [ETHUSDT]
+  from: 2021-01-01
+  to: 2021-06-01
+strategies:
+  - type: hodl
+  - type: macd
+    stake: 0.5
+"#;
+
+    fn write(extension: &str, content: &str) -> tempfile::NamedTempFile {
+        let mut file = Builder::new().suffix(extension).tempfile().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_toml() {
+        let file = write(".toml", TOML);
+        let experiment = Experiment::from_file(file.path()).unwrap();
+        assert_eq!(experiment.data.symbols, vec!["ETHUSDT", "BTCUSDT"]);
+        assert_eq!(experiment.data.from, NaiveDate::from_ymd(2021, 1, 1));
+        assert_eq!(experiment.risk_free_rate, 0.02);
+        assert_eq!(experiment.benchmark.as_deref(), Some("BTCUSDT"));
+
+        let settings = experiment.settings();
+        assert_eq!(settings.capital, 5000.);
+        assert_eq!(settings.timing, ExecutionTiming::SameBarClose);
+        assert_eq!(
+            settings.trading_fee,
+            TradingFee::MakerTaker {
+                maker: 0.0002,
+                taker: 0.0004
+            }
+        );
+
+        let trailing = &experiment.strategies[0];
+        assert_eq!(trailing.name(), "MACD+TS");
+        assert_eq!(trailing.settings(&settings).trailing_stop, 0.1);
+        assert_eq!(experiment.strategies[1].name(), "DCA 200");
+        assert_eq!(experiment.strategies[1].settings(&settings).dca_amount, 200.);
+    }
+
+    #[test]
+    fn test_yaml_defaults() {
+        let file = write(".yaml", YAML);
+        let experiment = Experiment::from_file(file.path()).unwrap();
+        assert_eq!(experiment.data.interval, "1h");
+        assert_eq!(experiment.account.capital, 1000.);
+        assert_eq!(experiment.benchmark, None);
+        assert_eq!(experiment.execution, ExecutionConfig::default());
+        assert_eq!(experiment.strategies[0], StrategyConfig::Hodl { name: None });
+        assert_eq!(experiment.strategies[1].strategy(), Strategy::Macd);
+    }
+
+    #[test]
+    fn test_examples() {
+        for example in [
+            "experiments/macd-vs-dca.toml",
+            "experiments/hodl-vs-dca.yaml",
+            "experiments/macd-grid.toml",
+        ] {
+            let experiment = Experiment::from_file(example);
+            assert!(experiment.is_ok(), "{}: {}", example, experiment.unwrap_err());
+        }
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let file = write(".yaml", &YAML.replace("stake: 0.5", "stak: 0.5"));
+        let error = Experiment::from_file(file.path()).unwrap_err().to_string();
+        assert!(error.contains("unknown field `stak`"), "{}", error);
+    }
+
+    #[test]
+    fn test_validation() {
+        let content = YAML
+            .replace("2021-06-01", "2020-06-01")
+            .replace("stake: 0.5", "stake: 1.5")
+            .replace("[ETHUSDT]", "[ETH/USDT]")
+            .replace("strategies:", "benchmark: BTC/USDT\nstrategies:");
+        let file = write(".yml", &content);
+        let problems = match Experiment::from_file(file.path()) {
+            Err(ConfigError::Invalid(problems)) => problems,
+            result => panic!("unexpected {:?}", result),
+        };
+        assert_eq!(
+            problems,
+            vec![
+                "data.symbols[0] [ETH/USDT] is not a symbol like ETHUSDT",
+                "benchmark [BTC/USDT] is not a symbol like BTCUSDT",
+                "data.from (2021-01-01) must be before data.to (2020-06-01)",
+                "strategies[1].stake must be above 0 and at most 1, got 1.5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_periods() {
+        let periods = "  periods:\n    - { from: 2021-01-01, to: 2021-03-01 }\n    - { from: 2021-03-01, to: 2021-07-01 }\n";
+        let file = write(".yaml", &YAML.replace("strategies:", &format!("{}strategies:", periods)));
+        let problems = match Experiment::from_file(file.path()) {
+            Err(ConfigError::Invalid(problems)) => problems,
+            result => panic!("unexpected {:?}", result),
+        };
+        assert_eq!(
+            problems,
+            vec![
+                "data.periods[1] 2021-03-01..2021-07-01 must lie within data.from (2021-01-01) and data.to (2021-06-01)"
+            ]
+        );
+
+        let file = write(".yaml", YAML);
+        let data = Experiment::from_file(file.path()).unwrap().data;
+        assert_eq!(data.periods(), vec!["2021-01-01..2021-06-01".parse().unwrap()]);
+        assert!("2021-01-01".parse::<Period>().is_err());
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = r#"
+grid:
+  - type: macd
+    macd: { fast: [8, 12], slow: [26] }
+    stake: [0.5, 2.0, 3.0]
+  - type: dca
+    frequency: [weekly, monthly]
+  - type: macd-trailing-stop
+    macd: { fast: [30] }
+"#;
+        let file = write(".yaml", &YAML.replace("strategies:", &format!("{}strategies:", grid)));
+        let problems = match Experiment::from_file(file.path()) {
+            Err(ConfigError::Invalid(problems)) => problems,
+            result => panic!("unexpected {:?}", result),
+        };
+        assert_eq!(
+            problems,
+            vec![
+                "grid[0].stake must be above 0 and at most 1, got 2",
+                "grid[0].stake must be above 0 and at most 1, got 3",
+                "grid[2] has nothing to run, every list needs a value and a fast period below a slow one",
+            ]
+        );
+
+        let data = &YAML[..YAML.find("strategies:").unwrap()];
+        let file = write(".yaml", &format!("{}grid:\n  - type: hodl\n  - type: dca\n", data));
+        let experiment = Experiment::from_file(file.path()).unwrap();
+        assert!(experiment.strategies.is_empty());
+        assert_eq!(experiment.grid[1].strategies()[0].name(), "DCA 100 monthly");
+    }
+
+    #[test]
+    fn test_walk_forward() {
+        let walk_forward = "walk_forward:\n  in_sample_days: 200\n";
+        let file = write(".yaml", &YAML.replace("strategies:", &format!("{}strategies:", walk_forward)));
+        let problems = match Experiment::from_file(file.path()) {
+            Err(ConfigError::Invalid(problems)) => problems,
+            result => panic!("unexpected {:?}", result),
+        };
+        assert_eq!(
+            problems,
+            vec!["walk_forward.in_sample_days (200) leaves nothing out of sample in 2021-01-01..2021-06-01"]
+        );
+
+        let walk_forward = "walk_forward:\n  in_sample_days: 90\n";
+        let file = write(".yaml", &YAML.replace("strategies:", &format!("{}strategies:", walk_forward)));
+        let experiment = Experiment::from_file(file.path()).unwrap();
+        assert_eq!(
+            experiment.walk_forward,
+            Some(WalkForwardConfig {
+                in_sample_days: 90,
+                out_of_sample_days: 60
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let file = write(".json", "{}");
+        assert!(matches!(
+            Experiment::from_file(file.path()),
+            Err(ConfigError::UnknownFormat(_))
+        ));
+    }
+}
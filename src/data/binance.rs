@@ -7,7 +7,6 @@ use yata::core::OHLCV;
 
 use chrono::prelude::*;
 use chrono::{Duration, NaiveDateTime, Utc};
-use reqwest;
 use tempfile::tempfile;
 
 fn is_current_month(year: i32, month: u32) -> bool {
@@ -32,8 +31,7 @@ fn binance_data_url(symbol: String, interval: String, year: i32, month: u32, day
         "monthly" => format!("{}-{}-{}-{:02}.zip", symbol, interval, year, month),
         _ => panic!("Not expected folder type"),
     };
-    let url = format!("{}/{}/{}/{}", base_url, symbol, interval, file_name);
-    url
+    format!("{}/{}/{}/{}", base_url, symbol, interval, file_name)
 }
 
 async fn check_url_exists(url: String) -> bool {
@@ -88,7 +86,7 @@ impl OHLCV for BinanceKline {
     }
 }
 
-fn parse_binance_kline(data: &str) -> Option<BinanceKline> {
+pub(super) fn parse_binance_kline(data: &str) -> Option<BinanceKline> {
     if !data.contains(",") {
         return None;
     }
@@ -96,9 +94,9 @@ fn parse_binance_kline(data: &str) -> Option<BinanceKline> {
     let start_time: i64 = data.next().unwrap().parse().unwrap();
     let start_time = NaiveDateTime::from_timestamp(start_time / 1000, 0);
     let open: f64 = data.next().unwrap().parse().unwrap();
-    let close: f64 = data.next().unwrap().parse().unwrap();
     let high: f64 = data.next().unwrap().parse().unwrap();
     let low: f64 = data.next().unwrap().parse().unwrap();
+    let close: f64 = data.next().unwrap().parse().unwrap();
     let volume: f64 = data.next().unwrap().parse().unwrap();
     let end_time: i64 = data.next().unwrap().parse().unwrap();
     let end_time = NaiveDateTime::from_timestamp(end_time / 1000, 0);
@@ -116,7 +114,7 @@ fn parse_binance_kline(data: &str) -> Option<BinanceKline> {
 }
 
 fn advance_date(current_date: NaiveDate) -> NaiveDate {
-    let next_date = if !is_current_month(current_date.year(), current_date.month()) {
+    if !is_current_month(current_date.year(), current_date.month()) {
         if current_date.month() < 12 {
             NaiveDate::from_ymd(current_date.year(), current_date.month() + 1, 1)
         } else {
@@ -124,8 +122,7 @@ fn advance_date(current_date: NaiveDate) -> NaiveDate {
         }
     } else {
         current_date + Duration::days(1)
-    };
-    next_date
+    }
 }
 
 pub async fn get_kline_data(
@@ -152,10 +149,8 @@ pub async fn get_kline_data(
                 .unwrap();
             let content = read_zip_file(temp_file);
             for line in content.split("\n") {
-                let candle = parse_binance_kline(&line);
-                match candle {
-                    Some(data) => result.push(data),
-                    None => (),
+                if let Some(data) = parse_binance_kline(line) {
+                    result.push(data);
                 }
             }
         }
@@ -174,13 +169,13 @@ mod tests {
         let test_string: &str = "1635739200000,4191.50000000,4320.00000000,4146.30000000,4302.93000000,88831.99690000,1635753599999,376834938.78850900,216236,45666.95420000,193846769.34658200,0";
         let result = parse_binance_kline(test_string).unwrap();
         let expected = BinanceKline {
-            start_time: NaiveDate::from_ymd(2021, 11, 01).and_hms(4, 0, 0),
+            start_time: NaiveDate::from_ymd(2021, 11, 1).and_hms(4, 0, 0),
             open: 4191.5,
-            close: 4320.0,
-            high: 4146.3,
-            low: 4302.93,
+            high: 4320.0,
+            low: 4146.3,
+            close: 4302.93,
             volume: 88831.9969,
-            end_time: NaiveDate::from_ymd(2021, 11, 01).and_hms(7, 59, 59),
+            end_time: NaiveDate::from_ymd(2021, 11, 1).and_hms(7, 59, 59),
         };
 
         assert_eq!(result, expected);
@@ -0,0 +1,340 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use log::info;
+
+use super::binance::{get_kline_data, parse_binance_kline, BinanceKline};
+
+/// klines saved per symbol and interval, in the same csv layout binance publishes, next to the days already
+/// downloaded so gaps binance has no klines for are not asked for again
+pub struct KlineCache {
+    dir: PathBuf,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CacheEntry {
+    pub symbol: String,
+    pub interval: String,
+    pub klines: usize,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+fn format_kline(kline: &BinanceKline) -> String {
+    format!(
+        "{},{},{},{},{},{},{}",
+        kline.start_time.timestamp_millis(),
+        kline.open,
+        kline.high,
+        kline.low,
+        kline.close,
+        kline.volume,
+        kline.end_time.timestamp_millis() + 999
+    )
+}
+
+impl KlineCache {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, symbol: &str, interval: &str) -> PathBuf {
+        self.dir.join(format!("{}-{}.csv", symbol, interval))
+    }
+
+    fn fetched_path(&self, symbol: &str, interval: &str) -> PathBuf {
+        self.dir.join(format!("{}-{}.fetched", symbol, interval))
+    }
+
+    /// the days already downloaded for the symbol, whether binance had klines for them or not
+    pub fn fetched(&self, symbol: &str, interval: &str) -> io::Result<Vec<(NaiveDate, NaiveDate)>> {
+        let content = match fs::read_to_string(self.fetched_path(symbol, interval)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| {
+                let (from, to) = line.split_once(',')?;
+                Some((from.parse().ok()?, to.parse().ok()?))
+            })
+            .collect())
+    }
+
+    /// remember that the days from `from` until before `to` have been downloaded
+    pub fn record_fetched(&self, symbol: &str, interval: &str, from: NaiveDate, to: NaiveDate) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.fetched_path(symbol, interval))?;
+        writeln!(file, "{},{}", from, to)
+    }
+
+    /// everything cached for the symbol, empty if nothing is
+    pub fn load(&self, symbol: &str, interval: &str) -> io::Result<Vec<BinanceKline>> {
+        match fs::read_to_string(self.path(symbol, interval)) {
+            Ok(content) => Ok(content.lines().filter_map(parse_binance_kline).collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// merge `klines` into what is already cached, keeping one kline per start time
+    pub fn store(&self, symbol: &str, interval: &str, klines: &[BinanceKline]) -> io::Result<()> {
+        let mut merged = self.load(symbol, interval)?;
+        merged.extend_from_slice(klines);
+        merged.sort_by_key(|kline| kline.start_time);
+        merged.dedup_by_key(|kline| kline.start_time);
+
+        fs::create_dir_all(&self.dir)?;
+        let mut file = fs::File::create(self.path(symbol, interval))?;
+        for kline in merged.iter() {
+            writeln!(file, "{}", format_kline(kline))?;
+        }
+        Ok(())
+    }
+
+    pub fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut entries = Vec::new();
+        for file in dir {
+            let name = file?.file_name().to_string_lossy().to_string();
+            let (symbol, interval) = match name.strip_suffix(".csv").and_then(|stem| stem.split_once('-')) {
+                Some(key) => key,
+                None => continue,
+            };
+            let klines = self.load(symbol, interval)?;
+            entries.push(CacheEntry {
+                symbol: symbol.to_string(),
+                interval: interval.to_string(),
+                klines: klines.len(),
+                from: klines.first().map(|kline| kline.start_time),
+                to: klines.last().map(|kline| kline.end_time),
+            });
+        }
+        entries.sort_by(|a, b| (&a.symbol, &a.interval).cmp(&(&b.symbol, &b.interval)));
+        Ok(entries)
+    }
+
+    /// remove cached klines and the record of what was downloaded, for one symbol or for all of them, returning
+    /// how many kline files went
+    pub fn clear(&self, symbol: Option<&str>) -> io::Result<usize> {
+        let mut removed = 0;
+        for entry in self.entries()? {
+            if symbol.is_none_or(|symbol| symbol == entry.symbol) {
+                fs::remove_file(self.path(&entry.symbol, &entry.interval))?;
+                removed += 1;
+            }
+        }
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(removed),
+            Err(e) => return Err(e),
+        };
+        for file in dir {
+            let path = file?.path();
+            let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            let fetched_symbol = name.strip_suffix(".fetched").and_then(|stem| stem.split_once('-'));
+            if fetched_symbol.is_some_and(|(fetched, _)| symbol.is_none_or(|symbol| symbol == fetched)) {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// `range` without the days in `fetched`
+fn not_fetched(range: (NaiveDate, NaiveDate), fetched: &[(NaiveDate, NaiveDate)]) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut remaining = vec![range];
+    for (fetched_from, fetched_to) in fetched.iter() {
+        remaining = remaining
+            .into_iter()
+            .flat_map(|(from, to)| {
+                [(from, to.min(*fetched_from)), (from.max(*fetched_to), to)]
+                    .into_iter()
+                    .filter(|(from, to)| from < to)
+            })
+            .collect();
+    }
+    remaining
+}
+
+/// the days between `from` and `to` that `klines` leave out and that were never downloaded, a gap counts once at
+/// least a whole bar is missing
+fn missing_ranges(
+    klines: &[BinanceKline],
+    fetched: &[(NaiveDate, NaiveDate)],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let bar = match klines.first() {
+        Some(kline) => kline.end_time + Duration::seconds(1) - kline.start_time,
+        None => return not_fetched((from, to), fetched),
+    };
+    let end = to.and_hms(0, 0, 0);
+    let mut expected = from.and_hms(0, 0, 0);
+    let mut missing = Vec::new();
+    for kline in klines.iter().filter(|kline| kline.start_time >= from.and_hms(0, 0, 0) && kline.start_time < end) {
+        if kline.start_time - expected >= bar {
+            missing.push((expected.date(), (kline.start_time.date() + Duration::days(1)).min(to)));
+        }
+        expected = kline.end_time + Duration::seconds(1);
+    }
+    if end - expected >= bar {
+        missing.push((expected.date(), to));
+    }
+    missing.into_iter().flat_map(|range| not_fetched(range, fetched)).collect()
+}
+
+/// klines from `from` until before `to`, downloading and caching whatever the cache is missing in between
+pub async fn get_cached_kline_data(
+    cache: &KlineCache,
+    symbol: &str,
+    interval: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<BinanceKline> {
+    let mut klines = cache.load(symbol, interval).unwrap_or_default();
+    let fetched = cache.fetched(symbol, interval).unwrap_or_default();
+    let missing = missing_ranges(&klines, &fetched, from, to);
+    if missing.is_empty() {
+        info!("using cached klines for [{}/{}]", symbol, interval);
+    }
+    // binance may not have published yesterday yet, so only days before it count as downloaded for good
+    let published = Utc::today().naive_utc() - Duration::days(1);
+    for (gap_from, gap_to) in missing {
+        info!("[{}/{}] from [{}] to [{}] is not cached", symbol, interval, gap_from, gap_to);
+        let downloaded = get_kline_data(symbol, interval, gap_from, gap_to).await;
+        let stored = cache.store(symbol, interval, &downloaded).and_then(|()| match gap_to.min(published) {
+            settled if gap_from < settled => cache.record_fetched(symbol, interval, gap_from, settled),
+            _ => Ok(()),
+        });
+        if let Err(e) = stored {
+            info!("unable to cache klines for [{}/{}]: {}", symbol, interval, e);
+        }
+        klines.extend(downloaded);
+    }
+    klines.sort_by_key(|kline| kline.start_time);
+    klines.dedup_by_key(|kline| kline.start_time);
+    klines
+        .into_iter()
+        .filter(|kline| kline.start_time.date() >= from && kline.start_time.date() < to)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn klines(from: NaiveDateTime, count: i64) -> Vec<BinanceKline> {
+        (0..count)
+            .map(|i| BinanceKline {
+                start_time: from + Duration::hours(i),
+                open: 100.5,
+                high: 110.25,
+                low: 90.,
+                close: 105.,
+                volume: 1234.5,
+                end_time: from + Duration::hours(i + 1) - Duration::seconds(1),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let dir = tempdir().unwrap();
+        let cache = KlineCache::new(dir.path());
+        let start = NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0);
+        assert!(cache.load("ETHUSDT", "1h").unwrap().is_empty());
+
+        cache.store("ETHUSDT", "1h", &klines(start, 3)).unwrap();
+        cache.store("ETHUSDT", "1h", &klines(start + Duration::hours(2), 3)).unwrap();
+        assert_eq!(cache.load("ETHUSDT", "1h").unwrap(), klines(start, 5));
+    }
+
+    #[test]
+    fn test_missing_ranges() {
+        let dir = tempdir().unwrap();
+        let cache = KlineCache::new(dir.path());
+        let date = |month, day| NaiveDate::from_ymd(2021, month, day);
+        let january = date(1, 1).and_hms(0, 0, 0);
+        let june = date(6, 1).and_hms(0, 0, 0);
+        cache.store("ETHUSDT", "1h", &klines(january, 24 * 59)).unwrap();
+        cache.store("ETHUSDT", "1h", &klines(june, 24 * 61)).unwrap();
+        let cached = cache.load("ETHUSDT", "1h").unwrap();
+
+        // asking across both leaves out the months between them
+        assert_eq!(missing_ranges(&cached, &[], date(1, 1), date(8, 1)), vec![(date(3, 1), date(6, 2))]);
+        assert!(missing_ranges(&cached, &[], date(1, 10), date(2, 28)).is_empty());
+        assert!(missing_ranges(&cached, &[], date(6, 1), date(8, 1)).is_empty());
+        assert_eq!(
+            missing_ranges(&cached, &[], date(7, 1), date(8, 10)),
+            vec![(date(8, 1), date(8, 10))]
+        );
+
+        // a bar missing in the middle of a day is a gap too
+        let mut holed = klines(january, 48);
+        holed.remove(30);
+        assert_eq!(missing_ranges(&holed, &[], date(1, 1), date(1, 3)), vec![(date(1, 2), date(1, 3))]);
+        assert_eq!(missing_ranges(&[], &[], date(1, 1), date(1, 3)), vec![(date(1, 1), date(1, 3))]);
+    }
+
+    #[test]
+    fn test_permanent_gap() {
+        let dir = tempdir().unwrap();
+        let cache = KlineCache::new(dir.path());
+        let date = |day| NaiveDate::from_ymd(2021, 1, day);
+        // binance has nothing for a few hours of the 2nd, as during an outage
+        let mut klines = klines(date(1).and_hms(0, 0, 0), 24 * 4);
+        klines.drain(36..40);
+        cache.store("ETHUSDT", "1h", &klines).unwrap();
+        let cached = cache.load("ETHUSDT", "1h").unwrap();
+        assert_eq!(missing_ranges(&cached, &[], date(1), date(5)), vec![(date(2), date(3))]);
+
+        // once the gap has been downloaded it is not asked for again
+        cache.record_fetched("ETHUSDT", "1h", date(2), date(3)).unwrap();
+        let fetched = cache.fetched("ETHUSDT", "1h").unwrap();
+        assert_eq!(fetched, vec![(date(2), date(3))]);
+        assert!(missing_ranges(&cached, &fetched, date(1), date(5)).is_empty());
+        // days after the cached klines are still missing, less whatever was downloaded of them
+        let fetched = [(date(2), date(3)), (date(5), date(6))];
+        assert_eq!(missing_ranges(&cached, &fetched, date(1), date(8)), vec![(date(6), date(8))]);
+        assert_eq!(
+            missing_ranges(&[], &fetched, date(1), date(8)),
+            vec![(date(1), date(2)), (date(3), date(5)), (date(6), date(8))]
+        );
+    }
+
+    #[test]
+    fn test_entries_and_clear() {
+        let dir = tempdir().unwrap();
+        let cache = KlineCache::new(dir.path());
+        let start = NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0);
+        cache.store("ETHUSDT", "1h", &klines(start, 2)).unwrap();
+        cache.store("BTCUSDT", "1d", &klines(start, 1)).unwrap();
+        cache.record_fetched("BTCUSDT", "1d", start.date(), start.date() + Duration::days(1)).unwrap();
+
+        let entries = cache.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].symbol, "ETHUSDT");
+        assert_eq!(entries[1].klines, 2);
+        assert_eq!(entries[1].to, Some(start + Duration::hours(2) - Duration::seconds(1)));
+
+        assert_eq!(cache.clear(Some("BTCUSDT")).unwrap(), 1);
+        assert!(cache.fetched("BTCUSDT", "1d").unwrap().is_empty());
+        assert_eq!(cache.clear(None).unwrap(), 1);
+        assert!(cache.entries().unwrap().is_empty());
+        assert_eq!(KlineCache::new(dir.path().join("missing")).clear(None).unwrap(), 0);
+    }
+}
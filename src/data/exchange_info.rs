@@ -0,0 +1,168 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct ExchangeInfo {
+    symbols: Vec<SymbolInfo>,
+}
+
+#[derive(Deserialize)]
+struct SymbolInfo {
+    symbol: String,
+    filters: Vec<SymbolFilter>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "filterType")]
+enum SymbolFilter {
+    #[serde(rename = "PRICE_FILTER", rename_all = "camelCase")]
+    Price { tick_size: String },
+    #[serde(rename = "LOT_SIZE", rename_all = "camelCase")]
+    LotSize { min_qty: String, step_size: String },
+    #[serde(rename = "MIN_NOTIONAL", alias = "NOTIONAL", rename_all = "camelCase")]
+    MinNotional { min_notional: String },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RejectReason {
+    MinQuantity,
+    MinNotional,
+    /// the order was more than the cash could pay for, the rejected quantity is the part left unfilled
+    InsufficientFunds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SymbolRules {
+    pub tick_size: f64,
+    pub step_size: f64,
+    pub min_quantity: f64,
+    pub min_notional: f64,
+}
+
+fn round_to_increment(value: f64, increment: f64, round: fn(f64) -> f64) -> f64 {
+    if increment <= 0. {
+        return value;
+    }
+    // tolerate float noise so that 0.3 / 0.1 still lands on 3 steps
+    let steps = round(value / increment + 1e-9);
+    let decimals = (-increment.log10()).ceil().max(0.) as i32;
+    let scale = 10f64.powi(decimals);
+    (steps * increment * scale).round() / scale
+}
+
+impl SymbolRules {
+    /// a position below this can not be sold, rounding down to the lot step leaves less than it behind
+    pub fn dust(&self) -> f64 {
+        self.step_size.max(self.min_quantity)
+    }
+
+    pub fn from_exchange_info<P: AsRef<Path>>(path: P, symbol: &str) -> Result<SymbolRules, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        let exchange_info: ExchangeInfo = serde_json::from_reader(reader)?;
+        let symbol_info = exchange_info
+            .symbols
+            .into_iter()
+            .find(|info| info.symbol == symbol)
+            .ok_or(format!("symbol [{}] not found in exchange info", symbol))?;
+
+        let mut rules = SymbolRules::default();
+        for filter in symbol_info.filters {
+            match filter {
+                SymbolFilter::Price { tick_size } => rules.tick_size = tick_size.parse()?,
+                SymbolFilter::LotSize { min_qty, step_size } => {
+                    rules.min_quantity = min_qty.parse()?;
+                    rules.step_size = step_size.parse()?;
+                }
+                SymbolFilter::MinNotional { min_notional } => rules.min_notional = min_notional.parse()?,
+                SymbolFilter::Other => (),
+            }
+        }
+        Ok(rules)
+    }
+
+    pub fn round_price(&self, price: f64) -> f64 {
+        round_to_increment(price, self.tick_size, f64::round)
+    }
+
+    pub fn round_quantity(&self, quantity: f64) -> f64 {
+        round_to_increment(quantity, self.step_size, f64::floor)
+    }
+
+    /// round an order to the symbol's increments, or explain why the exchange would reject it
+    pub fn apply(&self, quantity: f64, price: f64) -> Result<(f64, f64), RejectReason> {
+        let quantity = self.round_quantity(quantity);
+        let price = self.round_price(price);
+        if quantity <= 0. || quantity < self.min_quantity {
+            Err(RejectReason::MinQuantity)
+        } else if quantity * price < self.min_notional {
+            Err(RejectReason::MinNotional)
+        } else {
+            Ok((quantity, price))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const EXCHANGE_INFO: &str = r#"{
+        "timezone": "UTC",
+        "symbols": [
+            {
+                "symbol": "BTCUSDT",
+                "filters": [{"filterType": "PRICE_FILTER", "minPrice": "0.01", "maxPrice": "1000000.00", "tickSize": "0.01"}]
+            },
+            {
+                "symbol": "ETHUSDT",
+                "status": "TRADING",
+                "filters": [
+                    {"filterType": "PRICE_FILTER", "minPrice": "0.01", "maxPrice": "1000000.00", "tickSize": "0.01"},
+                    {"filterType": "LOT_SIZE", "minQty": "0.00010000", "maxQty": "9000.00000000", "stepSize": "0.00010000"},
+                    {"filterType": "ICEBERG_PARTS", "limit": 10},
+                    {"filterType": "NOTIONAL", "minNotional": "5.00000000", "applyMinToMarket": true}
+                ]
+            }
+        ]
+    }"#;
+
+    fn rules() -> SymbolRules {
+        SymbolRules {
+            tick_size: 0.01,
+            step_size: 0.0001,
+            min_quantity: 0.0001,
+            min_notional: 5.,
+        }
+    }
+
+    #[test]
+    fn test_from_exchange_info() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(EXCHANGE_INFO.as_bytes()).unwrap();
+        let result = SymbolRules::from_exchange_info(file.path(), "ETHUSDT").unwrap();
+        assert_eq!(result, rules());
+        assert!(SymbolRules::from_exchange_info(file.path(), "DOGEUSDT").is_err());
+    }
+
+    #[test]
+    fn test_rounding() {
+        assert_eq!(rules().round_quantity(1.23456789), 1.2345);
+        assert_eq!(rules().round_quantity(0.3), 0.3);
+        assert_eq!(rules().round_price(4302.937), 4302.94);
+    }
+
+    #[test]
+    fn test_apply() {
+        assert_eq!(rules().apply(0.012345, 4000.), Ok((0.0123, 4000.)));
+        assert_eq!(rules().apply(0.00005, 4000.), Err(RejectReason::MinQuantity));
+        assert_eq!(rules().apply(0.001, 4000.), Err(RejectReason::MinNotional));
+    }
+}
@@ -1,2 +1,29 @@
 mod binance;
-pub use binance::{get_kline_data, BinanceKline};
\ No newline at end of file
+pub use binance::BinanceKline;
+
+mod cache;
+pub use cache::{get_cached_kline_data, CacheEntry, KlineCache};
+
+mod exchange_info;
+pub use exchange_info::{RejectReason, SymbolRules};
+
+/// `count` hourly klines from `start` swinging around 100 like a sine wave, drifting by `trend` a bar
+#[cfg(test)]
+pub fn test_klines(start: chrono::NaiveDateTime, count: i64, trend: f64) -> Vec<BinanceKline> {
+    use chrono::Duration;
+
+    (0..count)
+        .map(|i| {
+            let price = 100. + (i as f64 / 10.).sin() * 10. + i as f64 * trend;
+            BinanceKline {
+                start_time: start + Duration::hours(i),
+                open: price,
+                high: price + 1.,
+                low: price - 1.,
+                close: price,
+                volume: 1000.,
+                end_time: start + Duration::hours(i + 1) - Duration::seconds(1),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,601 @@
+use crate::account::{Account, BuySellIndicator};
+use crate::config::{Period, StrategyConfig};
+use crate::data::BinanceKline;
+use crate::grid::GridResult;
+use crate::metrics::{Performance, RiskRatios, TradeStats};
+use crate::walk_forward::WalkForward;
+use chrono::NaiveDateTime;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// bumped whenever a field is renamed or removed, new fields are only ever appended
+pub const SCHEMA_VERSION: u32 = 1;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// headline numbers of one run, ratios are `None` when they are undefined
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SummaryRecord {
+    pub symbol: String,
+    pub strategy: String,
+    pub start: String,
+    pub end: String,
+    pub net_contribution: f64,
+    pub equity: f64,
+    pub fees_paid: f64,
+    /// profit over the net contribution, where `time_weighted_return` chains deposits and withdrawals out
+    pub total_return: Option<f64>,
+    pub cagr: Option<f64>,
+    pub time_weighted_return: f64,
+    pub money_weighted_return: Option<f64>,
+    pub max_drawdown: f64,
+    pub volatility: f64,
+    pub sharpe: Option<f64>,
+    pub sortino: Option<f64>,
+    pub calmar: Option<f64>,
+    pub omega: Option<f64>,
+    pub trades: usize,
+    pub round_trips: usize,
+    pub win_rate: Option<f64>,
+    pub profit_factor: Option<f64>,
+    pub expectancy: Option<f64>,
+    /// `from..to` the run was cut to
+    pub period: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradeRecord {
+    pub symbol: String,
+    pub strategy: String,
+    pub timestamp: String,
+    /// BUY or SELL
+    pub side: &'static str,
+    pub quantity: f64,
+    pub price: f64,
+    pub received_quantity: f64,
+    pub fee: f64,
+    pub slippage: f64,
+    pub period: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EquityRecord {
+    pub symbol: String,
+    pub strategy: String,
+    pub timestamp: String,
+    pub cash: f64,
+    pub quantity: f64,
+    pub price: f64,
+    pub market_value: f64,
+    pub equity: f64,
+    pub fees: f64,
+    pub interest_earned: f64,
+    pub staking_rewards: f64,
+    pub period: String,
+}
+
+/// everything exported about one run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunExport {
+    pub summary: SummaryRecord,
+    pub trades: Vec<TradeRecord>,
+    pub equity_curve: Vec<EquityRecord>,
+}
+
+/// one parameter combination of a grid search, parameters the strategy does not take are empty
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GridRecord {
+    pub symbol: String,
+    pub period: String,
+    pub strategy: String,
+    pub fast: Option<u8>,
+    pub slow: Option<u8>,
+    pub signal: Option<u8>,
+    pub stake: Option<f64>,
+    pub trailing_stop: Option<f64>,
+    pub amount: Option<f64>,
+    pub frequency: Option<&'static str>,
+    pub equity: f64,
+    pub total_return: f64,
+    pub cagr: Option<f64>,
+    pub max_drawdown: f64,
+    pub volatility: f64,
+    pub sharpe: Option<f64>,
+    pub sortino: Option<f64>,
+    pub calmar: Option<f64>,
+}
+
+impl GridRecord {
+    pub fn new(result: &GridResult) -> Self {
+        let row = &result.row;
+        let mut record = GridRecord {
+            symbol: row.symbol.clone(),
+            period: row.period.clone(),
+            strategy: row.strategy.clone(),
+            fast: None,
+            slow: None,
+            signal: None,
+            stake: None,
+            trailing_stop: None,
+            amount: None,
+            frequency: None,
+            equity: row.equity,
+            total_return: row.performance.total_return,
+            cagr: row.performance.cagr,
+            max_drawdown: row.performance.max_drawdown().map_or(0., |drawdown| drawdown.depth),
+            volatility: row.ratios.volatility,
+            sharpe: row.ratios.sharpe,
+            sortino: row.ratios.sortino,
+            calmar: row.ratios.calmar,
+        };
+        match &result.strategy {
+            StrategyConfig::Macd { stake, macd, .. } | StrategyConfig::MacdTrailingStop { stake, macd, .. } => {
+                record.fast = Some(macd.fast);
+                record.slow = Some(macd.slow);
+                record.signal = Some(macd.signal);
+                record.stake = Some(*stake);
+            }
+            StrategyConfig::Dca { amount, frequency, .. } => {
+                record.amount = Some(*amount);
+                record.frequency = Some(frequency.name());
+            }
+            StrategyConfig::Hodl { .. } => (),
+        }
+        if let StrategyConfig::MacdTrailingStop { trailing_stop, .. } = &result.strategy {
+            record.trailing_stop = Some(*trailing_stop);
+        }
+        record
+    }
+}
+
+/// one walk-forward window, the parameters chosen in sample and how they did in and out of sample
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WalkForwardRecord {
+    pub symbol: String,
+    pub in_sample: String,
+    pub out_of_sample: String,
+    pub strategy: String,
+    pub fast: Option<u8>,
+    pub slow: Option<u8>,
+    pub signal: Option<u8>,
+    pub stake: Option<f64>,
+    pub trailing_stop: Option<f64>,
+    pub amount: Option<f64>,
+    pub frequency: Option<&'static str>,
+    pub in_sample_return: f64,
+    pub in_sample_sharpe: Option<f64>,
+    pub out_of_sample_return: f64,
+    pub out_of_sample_max_drawdown: f64,
+    pub out_of_sample_sharpe: Option<f64>,
+}
+
+/// one point of the starting capital compounded over the out-of-sample windows
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WalkForwardCapitalRecord {
+    pub symbol: String,
+    pub period: String,
+    pub timestamp: String,
+    pub compounded_capital: f64,
+}
+
+impl WalkForwardRecord {
+    pub fn from_walk(walk: &WalkForward) -> Vec<Self> {
+        walk.windows
+            .iter()
+            .map(|window| {
+                let chosen = GridRecord::new(&window.chosen);
+                let out_of_sample = &window.out_of_sample_row;
+                WalkForwardRecord {
+                    symbol: walk.symbol.clone(),
+                    in_sample: window.in_sample.to_string(),
+                    out_of_sample: window.out_of_sample.to_string(),
+                    strategy: chosen.strategy,
+                    fast: chosen.fast,
+                    slow: chosen.slow,
+                    signal: chosen.signal,
+                    stake: chosen.stake,
+                    trailing_stop: chosen.trailing_stop,
+                    amount: chosen.amount,
+                    frequency: chosen.frequency,
+                    in_sample_return: chosen.total_return,
+                    in_sample_sharpe: chosen.sharpe,
+                    out_of_sample_return: out_of_sample.performance.total_return,
+                    out_of_sample_max_drawdown: out_of_sample
+                        .performance
+                        .max_drawdown()
+                        .map_or(0., |drawdown| drawdown.depth),
+                    out_of_sample_sharpe: out_of_sample.ratios.sharpe,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    schema_version: u32,
+    runs: &'a [RunExport],
+}
+
+fn format_timestamp(timestamp: NaiveDateTime) -> String {
+    timestamp.format(TIMESTAMP_FORMAT).to_string()
+}
+
+impl RunExport {
+    pub fn new(
+        symbol: &str,
+        period: Period,
+        strategy: &str,
+        account: &Account,
+        klines: &[BinanceKline],
+        risk_free_rate: f64,
+    ) -> Self {
+        let period = period.to_string();
+        let performance = Performance::from_account(account);
+        let ratios = RiskRatios::from_account(account, risk_free_rate);
+        let stats = TradeStats::from_account(account, klines);
+        let summary = SummaryRecord {
+            symbol: symbol.to_string(),
+            strategy: strategy.to_string(),
+            start: format_timestamp(performance.start),
+            end: format_timestamp(performance.end),
+            net_contribution: account.net_contribution(),
+            equity: account.equity(),
+            fees_paid: account.fees_paid,
+            total_return: account.simple_return(),
+            cagr: performance.cagr,
+            time_weighted_return: account.time_weighted_return(),
+            money_weighted_return: account.money_weighted_return(),
+            max_drawdown: performance.max_drawdown().map_or(0., |drawdown| drawdown.depth),
+            volatility: ratios.volatility,
+            sharpe: ratios.sharpe,
+            sortino: ratios.sortino,
+            calmar: ratios.calmar,
+            omega: ratios.omega,
+            trades: account.trade_history.len(),
+            round_trips: stats.count(),
+            win_rate: stats.win_rate(),
+            profit_factor: stats.profit_factor(),
+            expectancy: stats.expectancy(),
+            period: period.clone(),
+        };
+        let trades = account
+            .trade_history
+            .iter()
+            .map(|trade| TradeRecord {
+                symbol: symbol.to_string(),
+                strategy: strategy.to_string(),
+                timestamp: format_timestamp(trade.timestamp()),
+                side: match trade.buy_sell_indicator() {
+                    BuySellIndicator::Buy => "BUY",
+                    BuySellIndicator::Sell => "SELL",
+                },
+                quantity: trade.quantity(),
+                price: trade.price(),
+                received_quantity: trade.received_quantity(),
+                fee: trade.fee(),
+                slippage: trade.slippage(),
+                period: period.clone(),
+            })
+            .collect();
+        let equity_curve = account
+            .equity_curve()
+            .iter()
+            .map(|point| EquityRecord {
+                symbol: symbol.to_string(),
+                strategy: strategy.to_string(),
+                timestamp: format_timestamp(point.timestamp),
+                cash: point.cash,
+                quantity: point.quantity,
+                price: point.price,
+                market_value: point.market_value,
+                equity: point.equity,
+                fees: point.fees,
+                interest_earned: point.interest_earned,
+                staking_rewards: point.staking_rewards,
+                period: period.clone(),
+            })
+            .collect();
+        Self {
+            summary,
+            trades,
+            equity_curve,
+        }
+    }
+}
+
+fn write_csv<'a, T: Serialize + 'a>(path: &Path, records: impl Iterator<Item = &'a T>) -> io::Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for record in records {
+        writer.serialize(record)?;
+    }
+    writer.flush()
+}
+
+/// write the runs into `dir`, `results.json` holds everything while the csv files hold one table each,
+/// returns the files written
+pub fn export_runs(runs: &[RunExport], dir: &Path, formats: &[ExportFormat]) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for format in formats {
+        match format {
+            ExportFormat::Json => {
+                let path = dir.join("results.json");
+                let writer = BufWriter::new(File::create(&path)?);
+                let export = JsonExport {
+                    schema_version: SCHEMA_VERSION,
+                    runs,
+                };
+                serde_json::to_writer_pretty(writer, &export)?;
+                written.push(path);
+            }
+            ExportFormat::Csv => {
+                let summary = dir.join("summary.csv");
+                write_csv(&summary, runs.iter().map(|run| &run.summary))?;
+                let trades = dir.join("trades.csv");
+                write_csv(&trades, runs.iter().flat_map(|run| run.trades.iter()))?;
+                let equity = dir.join("equity.csv");
+                write_csv(&equity, runs.iter().flat_map(|run| run.equity_curve.iter()))?;
+                written.extend([summary, trades, equity]);
+            }
+        }
+    }
+    Ok(written)
+}
+
+#[derive(Serialize)]
+struct JsonGridExport<'a> {
+    schema_version: u32,
+    grid: &'a [GridRecord],
+}
+
+/// write a grid search into `dir` as `grid.json` and `grid.csv`, returns the files written
+pub fn export_grid(results: &[GridResult], dir: &Path, formats: &[ExportFormat]) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let records: Vec<GridRecord> = results.iter().map(GridRecord::new).collect();
+    let mut written = Vec::new();
+    for format in formats {
+        let path = match format {
+            ExportFormat::Json => {
+                let path = dir.join("grid.json");
+                let writer = BufWriter::new(File::create(&path)?);
+                let export = JsonGridExport {
+                    schema_version: SCHEMA_VERSION,
+                    grid: &records,
+                };
+                serde_json::to_writer_pretty(writer, &export)?;
+                path
+            }
+            ExportFormat::Csv => {
+                let path = dir.join("grid.csv");
+                write_csv(&path, records.iter())?;
+                path
+            }
+        };
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[derive(Serialize)]
+struct JsonWalkForwardExport<'a> {
+    schema_version: u32,
+    windows: &'a [WalkForwardRecord],
+    compounded_capital: &'a [WalkForwardCapitalRecord],
+}
+
+/// write walk-forwards into `dir`, `walk_forward.csv` holds the windows and `walk_forward_capital.csv` the
+/// compounded capital, `walk_forward.json` both, returns the files written
+pub fn export_walk_forward(walks: &[WalkForward], dir: &Path, formats: &[ExportFormat]) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let windows: Vec<WalkForwardRecord> = walks.iter().flat_map(WalkForwardRecord::from_walk).collect();
+    let compounded_capital: Vec<WalkForwardCapitalRecord> = walks
+        .iter()
+        .flat_map(|walk| {
+            walk.compounded_capital.iter().map(move |(timestamp, capital)| WalkForwardCapitalRecord {
+                symbol: walk.symbol.clone(),
+                period: walk.period.to_string(),
+                timestamp: format_timestamp(*timestamp),
+                compounded_capital: *capital,
+            })
+        })
+        .collect();
+    let mut written = Vec::new();
+    for format in formats {
+        match format {
+            ExportFormat::Json => {
+                let path = dir.join("walk_forward.json");
+                let writer = BufWriter::new(File::create(&path)?);
+                let export = JsonWalkForwardExport {
+                    schema_version: SCHEMA_VERSION,
+                    windows: &windows,
+                    compounded_capital: &compounded_capital,
+                };
+                serde_json::to_writer_pretty(writer, &export)?;
+                written.push(path);
+            }
+            ExportFormat::Csv => {
+                let path = dir.join("walk_forward.csv");
+                write_csv(&path, windows.iter())?;
+                let capital = dir.join("walk_forward_capital.csv");
+                write_csv(&capital, compounded_capital.iter())?;
+                written.extend([path, capital]);
+            }
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{Frequency, Position};
+    use crate::report::ComparisonRow;
+    use crate::walk_forward::WalkForwardWindow;
+    use chrono::{Duration, NaiveDate};
+    use tempfile::tempdir;
+
+    fn period() -> Period {
+        Period {
+            from: NaiveDate::from_ymd(2021, 11, 1),
+            to: NaiveDate::from_ymd(2021, 11, 2),
+        }
+    }
+
+    fn account() -> Account {
+        let start = NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0);
+        let position = Position {
+            quantity: 0.,
+            cost: 0.,
+        };
+        let mut account = Account::new(1000., position, start);
+        account.open(start, 5., 100., 1., 0.5);
+        account.mark_to_market(start + Duration::hours(1), 110.);
+        account.close(start + Duration::hours(2), 5., 120., 1., 0.);
+        account.mark_to_market(start + Duration::hours(2), 120.);
+        account
+    }
+
+    #[test]
+    fn test_run_export() {
+        let run = RunExport::new("ETHUSDT", period(), "MACD", &account(), &[], 0.);
+        assert_eq!(run.summary.start, "2021-11-01T00:00:00");
+        assert_eq!(run.summary.period, "2021-11-01..2021-11-02");
+        assert_eq!(run.summary.trades, 2);
+        assert_eq!(run.trades[0].side, "BUY");
+        assert_eq!(run.trades[0].slippage, 0.5);
+        assert_eq!(run.trades[1].side, "SELL");
+        assert_eq!(run.equity_curve.len(), account().equity_curve().len());
+    }
+
+    #[test]
+    fn test_total_return() {
+        let mut account = account();
+        let end = NaiveDate::from_ymd(2021, 11, 1).and_hms(3, 0, 0);
+        account.deposit(end, 1000.);
+        account.mark_to_market(end, 120.);
+        let summary = RunExport::new("ETHUSDT", period(), "MACD", &account, &[], 0.).summary;
+        // 98 made on 1000 before doubling the money in the account
+        assert!((summary.total_return.unwrap() - 98. / 2000.).abs() < 1e-9);
+        assert!((summary.time_weighted_return - 0.098).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_schema() {
+        let dir = tempdir().unwrap();
+        let runs = vec![RunExport::new("ETHUSDT", period(), "MACD", &account(), &[], 0.)];
+        let written = export_runs(&runs, dir.path(), &[ExportFormat::Json, ExportFormat::Csv]).unwrap();
+        assert_eq!(written.len(), 4);
+
+        let summary = fs::read_to_string(dir.path().join("summary.csv")).unwrap();
+        assert_eq!(
+            summary.lines().next().unwrap(),
+            "symbol,strategy,start,end,net_contribution,equity,fees_paid,total_return,cagr,time_weighted_return,\
+             money_weighted_return,max_drawdown,volatility,sharpe,sortino,calmar,omega,trades,round_trips,win_rate,\
+             profit_factor,expectancy,period"
+        );
+        let trades = fs::read_to_string(dir.path().join("trades.csv")).unwrap();
+        assert_eq!(
+            trades.lines().collect::<Vec<_>>(),
+            vec![
+                "symbol,strategy,timestamp,side,quantity,price,received_quantity,fee,slippage,period",
+                "ETHUSDT,MACD,2021-11-01T00:00:00,BUY,5.0,100.0,5.0,1.0,0.5,2021-11-01..2021-11-02",
+                "ETHUSDT,MACD,2021-11-01T02:00:00,SELL,5.0,120.0,5.0,1.0,0.0,2021-11-01..2021-11-02",
+            ]
+        );
+        let equity = fs::read_to_string(dir.path().join("equity.csv")).unwrap();
+        assert!(equity.starts_with(
+            "symbol,strategy,timestamp,cash,quantity,price,market_value,equity,fees,interest_earned,staking_rewards,\
+             period\n"
+        ));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("results.json")).unwrap()).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["runs"][0]["summary"]["strategy"], "MACD");
+        assert_eq!(json["runs"][0]["trades"][1]["side"], "SELL");
+    }
+
+    #[test]
+    fn test_grid_export() {
+        let dir = tempdir().unwrap();
+        let strategy = StrategyConfig::Dca {
+            name: Some("DCA 100 weekly".to_string()),
+            amount: 100.,
+            frequency: Frequency::Weekly,
+        };
+        let results = vec![GridResult {
+            row: ComparisonRow::new("ETHUSDT", "2021-11-01..2021-11-02", &strategy.name(), &account(), 0.),
+            strategy,
+        }];
+        let written = export_grid(&results, dir.path(), &[ExportFormat::Csv, ExportFormat::Json]).unwrap();
+        assert_eq!(written, vec![dir.path().join("grid.csv"), dir.path().join("grid.json")]);
+
+        let grid = fs::read_to_string(dir.path().join("grid.csv")).unwrap();
+        let lines: Vec<&str> = grid.lines().collect();
+        assert_eq!(
+            lines[0],
+            "symbol,period,strategy,fast,slow,signal,stake,trailing_stop,amount,frequency,equity,total_return,cagr,\
+             max_drawdown,volatility,sharpe,sortino,calmar"
+        );
+        assert!(lines[1].starts_with("ETHUSDT,2021-11-01..2021-11-02,DCA 100 weekly,,,,,,100.0,weekly,"));
+    }
+
+    #[test]
+    fn test_walk_forward_export() {
+        let dir = tempdir().unwrap();
+        let strategy = StrategyConfig::Hodl { name: None };
+        let account = account();
+        let period = |from, to| Period {
+            from: NaiveDate::from_ymd(2021, 11, from),
+            to: NaiveDate::from_ymd(2021, 11, to),
+        };
+        let row = |period: Period| ComparisonRow::new("ETHUSDT", &period.to_string(), "HODL", &account, 0.);
+        let start = NaiveDate::from_ymd(2021, 11, 2).and_hms(0, 0, 0);
+        let walk = WalkForward {
+            symbol: "ETHUSDT".to_string(),
+            period: period(1, 3),
+            windows: vec![WalkForwardWindow {
+                in_sample: period(1, 2),
+                out_of_sample: period(2, 3),
+                chosen: GridResult {
+                    strategy,
+                    row: row(period(1, 2)),
+                },
+                out_of_sample_row: row(period(2, 3)),
+            }],
+            compounded_capital: vec![(start, 1000.), (start + Duration::hours(1), 1010.)],
+            performance: None,
+            ratios: None,
+        };
+        let written = export_walk_forward(&[walk], dir.path(), &[ExportFormat::Csv, ExportFormat::Json]).unwrap();
+        assert_eq!(written.len(), 3);
+
+        let windows = fs::read_to_string(dir.path().join("walk_forward.csv")).unwrap();
+        let lines: Vec<&str> = windows.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("ETHUSDT,2021-11-01..2021-11-02,2021-11-02..2021-11-03,HODL,,,,,,,,"));
+        let capital = fs::read_to_string(dir.path().join("walk_forward_capital.csv")).unwrap();
+        assert_eq!(
+            capital.lines().collect::<Vec<&str>>(),
+            vec![
+                "symbol,period,timestamp,compounded_capital",
+                "ETHUSDT,2021-11-01..2021-11-03,2021-11-02T00:00:00,1000.0",
+                "ETHUSDT,2021-11-01..2021-11-03,2021-11-02T01:00:00,1010.0",
+            ]
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.path().join("walk_forward.json")).unwrap()).unwrap();
+        assert_eq!(json["windows"][0]["strategy"], "HODL");
+        assert_eq!(json["compounded_capital"][1]["compounded_capital"], 1010.);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::account::Frequency;
+use crate::comparison::{run_cells, Dataset};
+use crate::config::{default_dca_amount, default_stake, default_trailing_stop, StrategyConfig};
+use crate::report::{ComparisonRow, RankBy};
+use crate::traders::MacdPeriods;
+use serde::Deserialize;
+
+fn default_stakes() -> Vec<f64> {
+    vec![default_stake()]
+}
+
+fn default_trailing_stops() -> Vec<f64> {
+    vec![default_trailing_stop()]
+}
+
+fn default_amounts() -> Vec<f64> {
+    vec![default_dca_amount()]
+}
+
+fn default_frequencies() -> Vec<Frequency> {
+    vec![Frequency::default()]
+}
+
+/// MACD periods to try, every combination yata accepts is run
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MacdGrid {
+    pub fast: Vec<u8>,
+    pub slow: Vec<u8>,
+    pub signal: Vec<u8>,
+}
+
+impl Default for MacdGrid {
+    fn default() -> Self {
+        let macd = MacdPeriods::default();
+        Self {
+            fast: vec![macd.fast],
+            slow: vec![macd.slow],
+            signal: vec![macd.signal],
+        }
+    }
+}
+
+impl MacdGrid {
+    /// leaves out the combinations with the fast period not below the slow one
+    pub fn periods(&self) -> Vec<MacdPeriods> {
+        let mut periods = Vec::new();
+        for fast in self.fast.iter() {
+            for slow in self.slow.iter() {
+                for signal in self.signal.iter() {
+                    let macd = MacdPeriods {
+                        fast: *fast,
+                        slow: *slow,
+                        signal: *signal,
+                    };
+                    if macd.is_valid() {
+                        periods.push(macd);
+                    }
+                }
+            }
+        }
+        periods
+    }
+}
+
+/// the values to try for each parameter of one strategy, every combination of them is run
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum GridConfig {
+    Macd {
+        #[serde(default)]
+        macd: MacdGrid,
+        #[serde(default = "default_stakes")]
+        stake: Vec<f64>,
+    },
+    MacdTrailingStop {
+        #[serde(default)]
+        macd: MacdGrid,
+        #[serde(default = "default_stakes")]
+        stake: Vec<f64>,
+        #[serde(default = "default_trailing_stops")]
+        trailing_stop: Vec<f64>,
+    },
+    /// nothing to sweep, a baseline for the rest
+    Hodl,
+    Dca {
+        #[serde(default = "default_amounts")]
+        amount: Vec<f64>,
+        #[serde(default = "default_frequencies")]
+        frequency: Vec<Frequency>,
+    },
+}
+
+/// a fraction as a percentage to two decimals, trailing zeros dropped so 0.07 reads 7 rather than 7.000000000000001
+fn percent(fraction: f64) -> String {
+    let percent = format!("{:.2}", fraction * 100.);
+    percent.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+impl GridConfig {
+    /// every combination, each named after its parameters
+    pub fn strategies(&self) -> Vec<StrategyConfig> {
+        let mut strategies = Vec::new();
+        match self {
+            GridConfig::Macd { macd, stake } => {
+                for macd in macd.periods() {
+                    for stake in stake {
+                        strategies.push(StrategyConfig::Macd {
+                            name: Some(format!("MACD {} {}%", macd, percent(*stake))),
+                            stake: *stake,
+                            macd,
+                        });
+                    }
+                }
+            }
+            GridConfig::MacdTrailingStop {
+                macd,
+                stake,
+                trailing_stop,
+            } => {
+                for macd in macd.periods() {
+                    for stake in stake {
+                        for trailing_stop in trailing_stop {
+                            strategies.push(StrategyConfig::MacdTrailingStop {
+                                name: Some(format!(
+                                    "MACD+TS {} {}% ts {}%",
+                                    macd,
+                                    percent(*stake),
+                                    percent(*trailing_stop)
+                                )),
+                                stake: *stake,
+                                trailing_stop: *trailing_stop,
+                                macd,
+                            });
+                        }
+                    }
+                }
+            }
+            GridConfig::Hodl => strategies.push(StrategyConfig::Hodl { name: None }),
+            GridConfig::Dca { amount, frequency } => {
+                for amount in amount {
+                    for frequency in frequency {
+                        strategies.push(StrategyConfig::Dca {
+                            name: Some(format!("DCA {} {}", amount, frequency.name())),
+                            amount: *amount,
+                            frequency: *frequency,
+                        });
+                    }
+                }
+            }
+        }
+        strategies
+    }
+}
+
+/// one combination's run on one dataset, only the metrics are kept so that large grids fit in memory
+#[derive(Debug, Clone)]
+pub struct GridResult {
+    pub strategy: StrategyConfig,
+    pub row: ComparisonRow,
+}
+
+/// every combination on every dataset, keeping each run's metrics and dropping its account
+pub fn grid_search(datasets: &[Dataset], strategies: &[StrategyConfig], risk_free_rate: f64) -> Vec<Vec<GridResult>> {
+    run_cells(datasets, strategies, |dataset, strategy, account| {
+        let period = dataset.period.to_string();
+        GridResult {
+            strategy: strategy.clone(),
+            row: ComparisonRow::new(&dataset.symbol, &period, &strategy.name(), &account, risk_free_rate),
+        }
+    })
+}
+
+/// the result scoring highest on `objective`, the first one among equals
+pub fn best(results: &[GridResult], objective: RankBy) -> Option<&GridResult> {
+    results.iter().fold(None, |best, result| match best {
+        Some(best) if best.row.score(objective) >= result.row.score(objective) => Some(best),
+        _ => Some(result),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Period;
+    use crate::data::test_klines;
+    use crate::strategy::StrategySettings;
+    use chrono::NaiveDate;
+
+    fn dataset() -> Dataset {
+        let klines = test_klines(NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0), 24 * 60, 0.01);
+        let period = Period {
+            from: NaiveDate::from_ymd(2021, 1, 1),
+            to: NaiveDate::from_ymd(2021, 3, 1),
+        };
+        Dataset::new("ETHUSDT", period, &klines, StrategySettings::default())
+    }
+
+    #[test]
+    fn test_combinations() {
+        let grid = GridConfig::Macd {
+            macd: MacdGrid {
+                fast: vec![8, 12, 30],
+                slow: vec![26],
+                signal: vec![5, 9],
+            },
+            stake: vec![0.5, 1.],
+        };
+        let strategies = grid.strategies();
+        assert_eq!(strategies.len(), 2 * 2 * 2);
+        assert_eq!(strategies[0].name(), "MACD 8/26/5 50%");
+        assert_eq!(strategies[7].name(), "MACD 12/26/9 100%");
+
+        let grid = GridConfig::MacdTrailingStop {
+            macd: MacdGrid::default(),
+            stake: vec![0.07],
+            trailing_stop: vec![0.075, 0.1],
+        };
+        let names: Vec<String> = grid.strategies().iter().map(StrategyConfig::name).collect();
+        assert_eq!(names, vec!["MACD+TS 12/26/9 7% ts 7.5%", "MACD+TS 12/26/9 7% ts 10%"]);
+
+        let grid = GridConfig::Dca {
+            amount: vec![50., 100.],
+            frequency: vec![Frequency::Weekly, Frequency::Monthly],
+        };
+        let names: Vec<String> = grid.strategies().iter().map(StrategyConfig::name).collect();
+        assert_eq!(names, vec!["DCA 50 weekly", "DCA 50 monthly", "DCA 100 weekly", "DCA 100 monthly"]);
+        assert_eq!(GridConfig::Hodl.strategies(), vec![StrategyConfig::Hodl { name: None }]);
+    }
+
+    #[test]
+    fn test_grid_search() {
+        let datasets = vec![dataset()];
+        let grid = GridConfig::Macd {
+            macd: MacdGrid {
+                fast: vec![6, 12],
+                ..MacdGrid::default()
+            },
+            stake: vec![0.5, 1.],
+        };
+        let strategies = grid.strategies();
+        let results = grid_search(&datasets, &strategies, 0.);
+        assert_eq!(results[0].len(), 4);
+        for (strategy, result) in strategies.iter().zip(results[0].iter()) {
+            assert_eq!(&result.strategy, strategy);
+            assert_eq!(result.row.strategy, strategy.name());
+            assert_eq!(result.row.period, "2021-01-01..2021-03-01");
+        }
+
+        let best = best(&results[0], RankBy::Return).unwrap();
+        assert!(results[0]
+            .iter()
+            .all(|result| result.row.performance.total_return <= best.row.performance.total_return));
+        // different periods trade differently
+        assert_ne!(results[0][0].row.equity, results[0][2].row.equity);
+    }
+}
@@ -0,0 +1,370 @@
+use crate::account::{Account, BuySellIndicator};
+use crate::data::BinanceKline;
+use crate::metrics::{growth_index, Performance, ReturnsTable, RiskRatios, TradeStats};
+use chrono::NaiveDateTime;
+use std::fmt::Write;
+
+const WIDTH: f64 = 960.;
+const HEIGHT: f64 = 280.;
+const MARGIN: f64 = 48.;
+/// points per line, longer series are thinned so the file stays small
+const MAX_POINTS: usize = 1500;
+const COLOURS: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse;margin:1em 0}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:right}\
+th:first-child,td:first-child{text-align:left}\
+svg{display:block;margin:1em 0;background:#fafafa}\
+.legend span{margin-right:1.5em}\
+.heatmap td{min-width:3.5em;font-size:small}";
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn format_pct(value: Option<f64>) -> String {
+    value.map_or("n/a".to_string(), |value| format!("{:.02}%", value * 100.))
+}
+
+fn format_ratio(value: Option<f64>) -> String {
+    value.map_or("n/a".to_string(), |value| format!("{:.02}", value))
+}
+
+/// every `step`th point and always the last one
+fn thin(series: &[(NaiveDateTime, f64)]) -> Vec<(NaiveDateTime, f64)> {
+    let step = series.len().div_ceil(MAX_POINTS).max(1);
+    let mut thinned: Vec<_> = series.iter().step_by(step).copied().collect();
+    if let (Some(last), Some(kept)) = (series.last(), thinned.last()) {
+        if last.0 != kept.0 {
+            thinned.push(*last);
+        }
+    }
+    thinned
+}
+
+/// maps timestamps and values into the plot area of one svg
+struct Chart {
+    start: i64,
+    end: i64,
+    low: f64,
+    high: f64,
+    svg: String,
+}
+
+impl Chart {
+    fn new(series: &[&[(NaiveDateTime, f64)]], label: impl Fn(f64) -> String) -> Chart {
+        let points = series.iter().flat_map(|series| series.iter());
+        let (mut start, mut end, mut low, mut high) = (i64::MAX, i64::MIN, f64::INFINITY, f64::NEG_INFINITY);
+        for (timestamp, value) in points {
+            start = start.min(timestamp.timestamp());
+            end = end.max(timestamp.timestamp());
+            low = low.min(*value);
+            high = high.max(*value);
+        }
+        if high - low < 1e-12 {
+            high = low + 1.;
+        }
+        let mut chart = Chart {
+            start,
+            end: end.max(start + 1),
+            low,
+            high,
+            svg: format!(
+                "<svg width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+                WIDTH, HEIGHT, WIDTH, HEIGHT
+            ),
+        };
+        chart.axes(label);
+        chart
+    }
+
+    fn x(&self, timestamp: NaiveDateTime) -> f64 {
+        let share = (timestamp.timestamp() - self.start) as f64 / (self.end - self.start) as f64;
+        MARGIN + share * (WIDTH - 2. * MARGIN)
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        HEIGHT - MARGIN - (value - self.low) / (self.high - self.low) * (HEIGHT - 2. * MARGIN)
+    }
+
+    fn axes(&mut self, label: impl Fn(f64) -> String) {
+        let (left, right, bottom) = (MARGIN, WIDTH - MARGIN, HEIGHT - MARGIN);
+        let _ = write!(
+            self.svg,
+            "<line x1=\"{left}\" y1=\"{MARGIN}\" x2=\"{left}\" y2=\"{bottom}\" stroke=\"#999\"/>\
+             <line x1=\"{left}\" y1=\"{bottom}\" x2=\"{right}\" y2=\"{bottom}\" stroke=\"#999\"/>",
+        );
+        for value in [self.low, (self.low + self.high) / 2., self.high] {
+            let y = self.y(value);
+            let _ = write!(
+                self.svg,
+                "<line x1=\"{left}\" y1=\"{y:.1}\" x2=\"{right}\" y2=\"{y:.1}\" stroke=\"#eee\"/>\
+                 <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"11\" text-anchor=\"end\">{}</text>",
+                left - 4.,
+                y + 4.,
+                label(value)
+            );
+        }
+        for (timestamp, anchor) in [(self.start, "start"), (self.end, "end")] {
+            let date = NaiveDateTime::from_timestamp(timestamp, 0).date();
+            let x = if anchor == "start" { left } else { right };
+            let _ = write!(
+                self.svg,
+                "<text x=\"{x}\" y=\"{:.1}\" font-size=\"11\" text-anchor=\"{anchor}\">{date}</text>",
+                bottom + 16.
+            );
+        }
+    }
+
+    fn line(&mut self, series: &[(NaiveDateTime, f64)], colour: &str) {
+        let points: Vec<String> = thin(series)
+            .iter()
+            .map(|(timestamp, value)| format!("{:.1},{:.1}", self.x(*timestamp), self.y(*value)))
+            .collect();
+        let _ = write!(
+            self.svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.2\" points=\"{}\"/>",
+            colour,
+            points.join(" ")
+        );
+    }
+
+    /// an upward triangle below a buy, a downward one above a sale
+    fn marker(&mut self, timestamp: NaiveDateTime, price: f64, side: BuySellIndicator) {
+        let (x, y) = (self.x(timestamp), self.y(price));
+        let (tip, colour) = match side {
+            BuySellIndicator::Buy => (9., "#2ca02c"),
+            BuySellIndicator::Sell => (-9., "#d62728"),
+        };
+        let points = format!("{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}", x, y, x - 5., y + tip, x + 5., y + tip);
+        let _ = write!(self.svg, "<polygon class=\"marker\" points=\"{}\" fill=\"{}\"/>", points, colour);
+    }
+
+    fn finish(mut self) -> String {
+        self.svg.push_str("</svg>");
+        self.svg
+    }
+}
+
+/// how far below its running peak the growth index is at each point
+fn drawdown_series(account: &Account) -> Vec<(NaiveDateTime, f64)> {
+    let mut peak = f64::NEG_INFINITY;
+    growth_index(account)
+        .into_iter()
+        .map(|(timestamp, value)| {
+            peak = peak.max(value);
+            (timestamp, value / peak - 1.)
+        })
+        .collect()
+}
+
+fn legend(names: &[&str]) -> String {
+    let entries: Vec<String> = names
+        .iter()
+        .zip(COLOURS.iter().cycle())
+        .map(|(name, colour)| format!("<span style=\"color:{}\">&#9632; {}</span>", colour, escape(name)))
+        .collect();
+    format!("<div class=\"legend\">{}</div>", entries.join(""))
+}
+
+/// green for gains and red for losses, stronger the further from zero up to ±20% a month
+fn heat(value: f64) -> String {
+    let strength = (value.abs() / 0.2).min(1.);
+    let fade = (255. - strength * 155.) as u8;
+    if value >= 0. {
+        format!("rgb({},255,{})", fade, fade)
+    } else {
+        format!("rgb(255,{},{})", fade, fade)
+    }
+}
+
+fn heatmap(account: &Account) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let mut html = String::from("<table class=\"heatmap\"><tr><th>year</th>");
+    for month in MONTHS {
+        let _ = write!(html, "<th>{}</th>", month);
+    }
+    html.push_str("<th>year</th></tr>");
+    for row in ReturnsTable::from_account(account).years {
+        let _ = write!(html, "<tr><td>{}</td>", row.year);
+        for month in row.months {
+            match month {
+                Some(value) => {
+                    let _ = write!(html, "<td style=\"background:{}\">{:+.1}%</td>", heat(value), value * 100.);
+                }
+                None => html.push_str("<td></td>"),
+            }
+        }
+        let _ = write!(
+            html,
+            "<td style=\"background:{}\"><b>{:+.1}%</b></td></tr>",
+            heat(row.total / 12.),
+            row.total * 100.
+        );
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// a single html file with inline svg charts, nothing is loaded from elsewhere when it is opened
+pub struct HtmlReport {
+    title: String,
+    risk_free_rate: f64,
+    body: String,
+}
+
+impl HtmlReport {
+    pub fn new(title: &str, risk_free_rate: f64) -> Self {
+        Self {
+            title: title.to_string(),
+            risk_free_rate,
+            body: String::new(),
+        }
+    }
+
+    /// metrics, equity and drawdown charts of every run on the symbol, then the price with each run's trades
+    pub fn add_symbol(&mut self, symbol: &str, klines: &[BinanceKline], runs: &[(String, Account)]) {
+        if runs.is_empty() {
+            return;
+        }
+        let _ = write!(self.body, "<h2>{}</h2>", escape(symbol));
+        self.metrics_table(klines, runs);
+
+        let names: Vec<&str> = runs.iter().map(|(name, _)| name.as_str()).collect();
+        let equity: Vec<Vec<(NaiveDateTime, f64)>> = runs
+            .iter()
+            .map(|(_, account)| {
+                account
+                    .equity_curve()
+                    .iter()
+                    .map(|point| (point.timestamp, point.equity))
+                    .collect()
+            })
+            .collect();
+        let drawdowns: Vec<Vec<(NaiveDateTime, f64)>> =
+            runs.iter().map(|(_, account)| drawdown_series(account)).collect();
+        for (heading, series, label) in [
+            ("Equity", &equity, (|value: f64| format!("${:.0}", value)) as fn(f64) -> String),
+            ("Drawdown", &drawdowns, |value: f64| format!("{:.0}%", value * 100.)),
+        ] {
+            let slices: Vec<&[(NaiveDateTime, f64)]> = series.iter().map(|series| series.as_slice()).collect();
+            let mut chart = Chart::new(&slices, label);
+            for (series, colour) in slices.iter().zip(COLOURS.iter().cycle()) {
+                chart.line(series, colour);
+            }
+            let _ = write!(self.body, "<h3>{}</h3>{}{}", heading, legend(&names), chart.finish());
+        }
+
+        let prices: Vec<(NaiveDateTime, f64)> = klines.iter().map(|kline| (kline.end_time, kline.close)).collect();
+        for (name, account) in runs {
+            let mut chart = Chart::new(&[&prices], |value| format!("{:.2}", value));
+            chart.line(&prices, "#555");
+            for trade in account.trade_history.iter() {
+                chart.marker(trade.timestamp(), trade.price(), trade.buy_sell_indicator());
+            }
+            let _ = write!(
+                self.body,
+                "<h3>{} {}: price and trades</h3>{}<h4>Monthly returns</h4>{}",
+                escape(symbol),
+                escape(name),
+                chart.finish(),
+                heatmap(account)
+            );
+        }
+    }
+
+    fn metrics_table(&mut self, klines: &[BinanceKline], runs: &[(String, Account)]) {
+        self.body.push_str(
+            "<table><tr><th>strategy</th><th>equity</th><th>contributed</th><th>return</th><th>CAGR</th>\
+             <th>max drawdown</th><th>volatility</th><th>Sharpe</th><th>Sortino</th><th>Calmar</th>\
+             <th>trades</th><th>win rate</th></tr>",
+        );
+        for (name, account) in runs {
+            let performance = Performance::from_account(account);
+            let ratios = RiskRatios::from_account(account, self.risk_free_rate);
+            let stats = TradeStats::from_account(account, klines);
+            let _ = write!(
+                self.body,
+                "<tr><td>{}</td><td>${:.02}</td><td>${:.02}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(name),
+                account.equity(),
+                account.net_contribution(),
+                format_pct(Some(performance.total_return)),
+                format_pct(performance.cagr),
+                format_pct(Some(performance.max_drawdown().map_or(0., |drawdown| drawdown.depth))),
+                format_pct(Some(ratios.volatility)),
+                format_ratio(ratios.sharpe),
+                format_ratio(ratios.sortino),
+                format_ratio(ratios.calmar),
+                account.trade_history.len(),
+                format_pct(stats.win_rate())
+            );
+        }
+        self.body.push_str("</table>");
+    }
+
+    pub fn finish(&self) -> String {
+        format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head>\
+             <body><h1>{}</h1>{}</body></html>\n",
+            escape(&self.title),
+            STYLE,
+            escape(&self.title),
+            self.body
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines;
+    use crate::strategy::{run_strategy, Strategy, StrategySettings};
+    use chrono::{Duration, NaiveDate};
+
+    fn klines() -> Vec<BinanceKline> {
+        test_klines(NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0), 24 * 60, 0.)
+    }
+
+    #[test]
+    fn test_thin() {
+        let start = NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0);
+        let series: Vec<_> = (0..4000).map(|i| (start + Duration::hours(i), i as f64)).collect();
+        let thinned = thin(&series);
+        assert!(thinned.len() <= MAX_POINTS + 1);
+        assert_eq!(thinned.first(), series.first());
+        assert_eq!(thinned.last(), series.last());
+    }
+
+    #[test]
+    fn test_report() {
+        let klines = klines();
+        let settings = StrategySettings::default();
+        let runs: Vec<(String, Account)> = [Strategy::Macd, Strategy::Hodl]
+            .iter()
+            .map(|strategy| (strategy.name().to_string(), run_strategy(*strategy, &klines, &settings)))
+            .collect();
+        let mut report = HtmlReport::new("ETHUSDT <test>", 0.);
+        report.add_symbol("ETHUSDT", &klines, &runs);
+        let html = report.finish();
+
+        assert!(html.contains("<title>ETHUSDT &lt;test&gt;</title>"));
+        // nothing external, everything is inline
+        assert!(!html.contains("http"));
+        assert!(!html.contains("<script"));
+        // equity, drawdown and one price chart per run
+        assert_eq!(html.matches("<svg").count(), 4);
+        let trades: usize = runs.iter().map(|(_, account)| account.trade_history.len()).sum();
+        assert_eq!(html.matches("class=\"marker\"").count(), trades);
+        assert_eq!(html.matches("class=\"heatmap\"").count(), 2);
+    }
+}
@@ -1,16 +1,21 @@
 use crate::data::BinanceKline;
 use crate::indicators::BinanceIndicatorInstance;
-use chrono::{Datelike, NaiveDateTime, NaiveDate};
+use crate::account::Frequency;
+use chrono::NaiveDate;
 use yata::core::{Action, Error, IndicatorResult, OHLCV};
 use yata::prelude::*;
 
-#[derive(Debug, Clone, Copy)]
-pub struct DCA {}
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DCA {
+    /// buys on the first kline of every period
+    pub frequency: Frequency,
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct DCAInstance {
     cfg: DCA,
-    last_timestamp: NaiveDateTime,
+    last_period: Option<NaiveDate>,
 }
 
 impl IndicatorConfig for DCA {
@@ -18,7 +23,7 @@ impl IndicatorConfig for DCA {
     const NAME: &'static str = "DCA";
     fn init<T: OHLCV>(self, _candle: &T) -> Result<Self::Instance, Error> {
         Ok(Self::Instance {
-            last_timestamp: NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0),  // FIXME: a magic date before crypto happens
+            last_period: None,
             cfg: self,
         })
     }
@@ -33,12 +38,6 @@ impl IndicatorConfig for DCA {
     }
 }
 
-impl Default for DCA {
-    fn default() -> Self {
-        Self {}
-    }
-}
-
 impl IndicatorInstance for DCAInstance {
     type Config = DCA;
 
@@ -53,15 +52,13 @@ impl IndicatorInstance for DCAInstance {
 
 impl BinanceIndicatorInstance for DCAInstance {
     fn next_binance_kline(&mut self, candle: &BinanceKline) -> IndicatorResult {
-        let current_time = (*candle).start_time;
-        let current_month = current_time.month();
-        let last_month = self.last_timestamp.month();
-        let action = if current_month != last_month {
+        let period = self.cfg.frequency.period_start(candle.start_time);
+        let action = if self.last_period != Some(period) {
             Action::Buy(1)
         } else {
             Action::None
         };
-        self.last_timestamp = current_time;
+        self.last_period = Some(period);
         IndicatorResult::new(&[], &[action])
     }
 }
\ No newline at end of file
@@ -3,7 +3,8 @@ use crate::indicators::BinanceIndicatorInstance;
 use yata::core::{Action, Error, IndicatorResult, OHLCV};
 use yata::prelude::*;
 
-#[derive(Debug, Clone, Copy)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct HODL {}
 
 #[derive(Debug, Clone, Copy)]
@@ -28,12 +29,6 @@ impl IndicatorConfig for HODL {
     }
 }
 
-impl Default for HODL {
-    fn default() -> Self {
-        Self {}
-    }
-}
-
 impl IndicatorInstance for HODLInstance {
     type Config = HODL;
 
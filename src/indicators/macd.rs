@@ -0,0 +1,53 @@
+use crate::data::BinanceKline;
+use crate::traders::MacdPeriods;
+use chrono::NaiveDateTime;
+use yata::prelude::*;
+
+/// MACD line and signal line for each kline after the first, as a MACD trader on `periods` sees them, the
+/// first kline only initialises the indicator
+pub fn macd_series(klines: &[BinanceKline], periods: MacdPeriods) -> Vec<(NaiveDateTime, f64, f64)> {
+    let first = match klines.first() {
+        Some(first) => first,
+        None => return Vec::new(),
+    };
+    let mut macd = periods.macd().init(first).expect("Unable to initialise MACD");
+    klines
+        .iter()
+        .skip(1)
+        .map(|kline| {
+            let result = macd.next(kline);
+            (kline.end_time, result.value(0), result.value(1))
+        })
+        .collect()
+}
+
+/// bars before the MACD is worth drawing, the signal line starts from the price rather than the MACD
+pub fn macd_warm_up(periods: MacdPeriods) -> usize {
+    periods.slow as usize + periods.signal as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_macd_series() {
+        let klines = test_klines(NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0), 24 * 30, 0.);
+        let macd = macd_series(&klines, MacdPeriods::default());
+        assert_eq!(macd.len(), klines.len() - 1);
+        assert_eq!(macd[0].0, klines[1].end_time);
+        assert!(macd.iter().all(|(_, line, signal)| line.is_finite() && signal.is_finite()));
+
+        // quicker periods follow the price more closely
+        let periods = MacdPeriods {
+            fast: 6,
+            slow: 13,
+            signal: 5,
+        };
+        let quick = macd_series(&klines, periods);
+        assert_ne!(quick[100].1, macd[100].1);
+        assert_eq!(macd_warm_up(periods), 18);
+    }
+}
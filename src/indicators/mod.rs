@@ -4,6 +4,9 @@ pub use dca::DCA;
 mod hodl;
 pub use hodl::HODL;
 
+mod macd;
+pub use macd::{macd_series, macd_warm_up};
+
 
 use crate::data::BinanceKline;
 use yata::core::IndicatorResult;
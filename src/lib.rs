@@ -0,0 +1,59 @@
+//! backtest trading strategies on binance klines
+//!
+//! - [`data`] downloads and caches klines and reads exchange trading rules
+//! - [`indicators`] and [`traders`] turn klines into buy and sell decisions
+//! - [`backtest`] and [`strategy`] replay the decisions into an [`account::Account`]
+//! - [`comparison`] runs many strategies over many symbols and periods in parallel, [`grid`] sweeps their parameters,
+//!   [`walk_forward`] reoptimises them on rolling windows and trades them out of sample
+//! - [`metrics`] measures the account, [`report`], [`export`], [`html`] and `charts` present it
+//! - [`runner`] puts the above together into the commands the binary offers
+//!
+//! the `charts` and `tui` modules are behind the features of the same name, both on by default
+//!
+//! ```
+//! use chrono::{Duration, NaiveDate};
+//! use crypto_strategy_analysis::data::BinanceKline;
+//! use crypto_strategy_analysis::metrics::Performance;
+//! use crypto_strategy_analysis::strategy::{run_strategy, Strategy, StrategySettings};
+//!
+//! let start = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+//! let klines: Vec<BinanceKline> = (0..24 * 60)
+//!     .map(|i| {
+//!         let price = 100. + (i as f64 / 20.).sin() * 10.;
+//!         BinanceKline {
+//!             start_time: start + Duration::hours(i),
+//!             open: price,
+//!             high: price + 1.,
+//!             low: price - 1.,
+//!             close: price,
+//!             volume: 1000.,
+//!             end_time: start + Duration::hours(i + 1) - Duration::seconds(1),
+//!         }
+//!     })
+//!     .collect();
+//! let account = run_strategy(Strategy::Macd, &klines, &StrategySettings::default());
+//! let performance = Performance::from_account(&account);
+//! assert!(!account.trade_history.is_empty());
+//! assert!(performance.total_return > -1.);
+//! ```
+
+pub mod account;
+pub mod backtest;
+#[cfg(feature = "charts")]
+pub mod charts;
+pub mod comparison;
+pub mod config;
+pub mod data;
+pub mod export;
+pub mod grid;
+pub mod html;
+pub mod indicators;
+pub mod metrics;
+pub mod orders;
+pub mod report;
+pub mod runner;
+pub mod strategy;
+pub mod traders;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod walk_forward;
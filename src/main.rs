@@ -1,132 +1,121 @@
-mod account;
-mod data;
-mod indicators;
-mod traders;
-
-use account::{Account, Position};
-use data::{BinanceKline, get_kline_data};
-use chrono::{Duration, NaiveDate, Utc};
-use traders::{DCATrader, GenericTrader, HODLTrader, MACDTrader, StakeSize, TradingFee};
+mod cli;
+
+use clap::Parser;
+use cli::{CacheAction, Cli, Command};
+use crypto_strategy_analysis::config::{ConfigError, Experiment};
+use crypto_strategy_analysis::data::KlineCache;
+use crypto_strategy_analysis::grid::{best, GridResult};
+use crypto_strategy_analysis::report::{comparison_table, walk_forward_table, ComparisonRow};
+use crypto_strategy_analysis::runner::{set_jobs, Runner};
+#[cfg(feature = "tui")]
+use crypto_strategy_analysis::tui::explore;
+use std::process;
 
 use env_logger::Env;
 use log::info;
 
-async fn download_kline() -> Vec<BinanceKline> {
-    let start_date = NaiveDate::from_ymd(2020, 1, 1);
-    let end_date = Utc::today() - Duration::days(1);
-    let end_date = end_date.naive_utc();
-    let symbol = "ETHUSDT";
-    let interval = "1h";
-    info!(
-        "download data from binance for [{}/{}] from [{}] to [{}]",
-        symbol, interval, start_date, end_date
-    );
-    let klines = get_kline_data(symbol, interval, start_date, end_date).await;
-    info!("downloaded [{}] klines", klines.len());
-    klines
-}
-
-fn initialise_acount(klines: Vec<BinanceKline>) -> Account {
-    info!("setting up account");
-    let first_kline = klines.first().expect("no klines fetched");
-    let start_time = first_kline.start_time;
-    let start_fund = 1000.0;
-    let start_position = Position {
-        quantity: 0.0,
-        cost: 0.0,
-    };
-    let account = Account::new(start_fund, start_position, start_time);
-    account
-}
-
-#[allow(dead_code)]
-fn initialise_macd_trader<'a>(
-    klines_iter: &'a mut dyn Iterator<Item = BinanceKline>,
-) -> MACDTrader {
-    info!("setting up macd trader");
-    let stake_size = StakeSize::FixPercentage(1.);
-    let trading_fee = TradingFee::PercentageFee(0.005);
-    let trader = MACDTrader::new(klines_iter, trading_fee, stake_size);
-    trader
-}
-
-#[allow(dead_code)]
-fn initialise_hodl_trader<'a>(
-    klines_iter: &'a mut dyn Iterator<Item = BinanceKline>,
-) -> HODLTrader {
-    info!("setting up hodl trader");
-    let trading_fee = TradingFee::PercentageFee(0.005);
-    let trader = HODLTrader::new(klines_iter, trading_fee);
-    trader
-}
-
-#[allow(dead_code)]
-fn initialise_dca_trader<'a>(klines_iter: &'a mut dyn Iterator<Item = BinanceKline>) -> DCATrader {
-    info!("setting up dca trader");
-    let trading_fee = TradingFee::PercentageFee(0.005);
-    let trader = DCATrader::new(klines_iter, trading_fee);
-    trader
-}
-
-fn loop_kline<'a, T>(trader: &mut T, account: &mut Account)
-where
-    T: GenericTrader<'a>,
-{
-    info!("running backtest");
-    loop {
-        let kline = trader.next_trade_session(account);
-        match kline {
-            Some(kline) => {
-                account.mark_to_market(kline.end_time, kline.close);
-            }
-            None => break,
+fn load_experiment(experiment: Result<Experiment, ConfigError>) -> Experiment {
+    match experiment {
+        Ok(experiment) => {
+            info!("running experiment {:?}", experiment);
+            experiment
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
         }
     }
 }
 
-
-async fn backtest_macd(klines: Vec<BinanceKline>) -> Account {
-    let mut klines_iter = klines.clone().into_iter();
-    let mut account = initialise_acount(klines);
-    let mut trader = initialise_macd_trader(&mut klines_iter);
-    loop_kline(&mut trader, &mut account);
-    account
-}
-
-
-async fn backtest_hodl(klines: Vec<BinanceKline>) -> Account {
-    let mut klines_iter = klines.clone().into_iter();
-    let mut account = initialise_acount(klines);
-    let mut trader = initialise_hodl_trader(&mut klines_iter);
-    loop_kline(&mut trader, &mut account);
-    account
-}
-
-
-async fn backtest_dca(klines: Vec<BinanceKline>) -> Account {
-    let mut klines_iter = klines.clone().into_iter();
-    let mut account = initialise_acount(klines);
-    let mut trader = initialise_dca_trader(&mut klines_iter);
-    loop_kline(&mut trader, &mut account);
-    account
-}
-
-async fn backtest(klines: Vec<BinanceKline>) -> (Account, Account, Account) {
-    let macd_account = backtest_macd(klines.clone());
-    let hodl_account = backtest_hodl(klines.clone());
-    let dca_account = backtest_dca(klines.clone());
-
-    futures::join!(macd_account, hodl_account, dca_account)
-}
-
 #[tokio::main]
 pub async fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
-    let klines = download_kline().await;
-    let result = backtest(klines);
-    let (macd_account, hodl_account, dca_account) = result.await;
+    let cli = Cli::parse();
+    let runner = Runner::new(KlineCache::new(&cli.cache_dir), &cli.exchange_info);
 
-    info!("MACD: {:?}", macd_account.profit_and_loss_history.last().unwrap());
-    info!("HODL: {:?}", hodl_account.profit_and_loss_history.last().unwrap());
-    info!("DCA : {:?}", dca_account.profit_and_loss_history.last().unwrap());
+    match &cli.command {
+        Command::Download(data) => {
+            for symbol in data.symbols.iter() {
+                runner.download(symbol, &data.interval, data.from, data.to()).await;
+            }
+        }
+        Command::Backtest(args) => {
+            let experiment = load_experiment(args.experiment());
+            runner.backtest(&experiment, &args.outputs()).await;
+        }
+        Command::Compare(args) => {
+            let experiment = load_experiment(args.experiment());
+            set_jobs(args.jobs);
+            let mut rows = runner.compare(&experiment, &args.outputs()).await;
+            print!("{}", comparison_table(&mut rows, args.rank_by));
+        }
+        Command::Report(args) => {
+            let experiment = load_experiment(args.experiment());
+            runner.report(&experiment, &args.outputs()).await;
+        }
+        #[cfg(feature = "tui")]
+        Command::Explore(args) => {
+            let experiment = load_experiment(args.experiment());
+            if let Err(e) = explore(runner.explore(&experiment).await) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        Command::Grid(args) => {
+            let experiment = load_experiment(args.experiment());
+            let objective = args.backtest.rank_by;
+            set_jobs(args.backtest.jobs);
+            let results = runner.grid(&experiment, &args.backtest.outputs()).await;
+            let bests: Vec<String> = results
+                .iter()
+                .filter_map(|results| best(results, objective))
+                .map(|best| {
+                    format!(
+                        "best on {} {}: {} with {} {:.04}",
+                        best.row.symbol,
+                        best.row.period,
+                        best.row.strategy,
+                        objective.name(),
+                        best.row.score(objective)
+                    )
+                })
+                .collect();
+            let mut rows: Vec<ComparisonRow> =
+                results.into_iter().flatten().map(|result: GridResult| result.row).collect();
+            print!("{}", comparison_table(&mut rows, objective));
+            for line in bests {
+                println!("{}", line);
+            }
+        }
+        Command::WalkForward(args) => {
+            let experiment = load_experiment(args.experiment());
+            let backtest = &args.grid.backtest;
+            set_jobs(backtest.jobs);
+            let walks = runner.walk_forward(&experiment, backtest.rank_by, &backtest.outputs()).await;
+            for walk in walks.iter() {
+                println!("{}", walk_forward_table(walk, backtest.rank_by));
+            }
+        }
+        Command::Cache { action } => match action {
+            CacheAction::List => match runner.cache().entries() {
+                Ok(entries) => {
+                    for entry in entries {
+                        println!(
+                            "{:<10} {:<4} {:>8} klines  {} - {}",
+                            entry.symbol,
+                            entry.interval,
+                            entry.klines,
+                            entry.from.map_or("-".to_string(), |from| from.to_string()),
+                            entry.to.map_or("-".to_string(), |to| to.to_string())
+                        );
+                    }
+                }
+                Err(e) => eprintln!("unable to read the cache at [{}]: {}", cli.cache_dir.display(), e),
+            },
+            CacheAction::Clear { symbol } => match runner.cache().clear(symbol.as_deref()) {
+                Ok(removed) => println!("removed {} cached file(s)", removed),
+                Err(e) => eprintln!("unable to clear the cache at [{}]: {}", cli.cache_dir.display(), e),
+            },
+        },
+    }
 }
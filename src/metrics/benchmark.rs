@@ -0,0 +1,145 @@
+use super::{bar_returns, growth_index, sampling_periods};
+use crate::account::Account;
+use std::collections::HashMap;
+
+/// a strategy measured against a benchmark run over the same bars
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchmarkStats {
+    /// annualised return not explained by the benchmark, after the risk-free rate
+    pub alpha: f64,
+    pub beta: f64,
+    /// annualised volatility of the return difference
+    pub tracking_error: f64,
+    pub information_ratio: Option<f64>,
+    /// the share of the benchmark's average rise captured on bars it went up
+    pub up_capture: Option<f64>,
+    /// the share of the benchmark's average fall captured on bars it went down
+    pub down_capture: Option<f64>,
+    pub correlation: Option<f64>,
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+fn covariance(a: &[f64], b: &[f64]) -> f64 {
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let sum: f64 = a.iter().zip(b).map(|(x, y)| (x - mean_a) * (y - mean_b)).sum();
+    sum / (a.len() as f64 - 1.).max(1.)
+}
+
+fn capture(pairs: impl Iterator<Item = (f64, f64)>) -> Option<f64> {
+    let (strategy, benchmark): (Vec<f64>, Vec<f64>) = pairs.unzip();
+    if benchmark.is_empty() {
+        None
+    } else {
+        Some(mean(&strategy) / mean(&benchmark))
+    }
+}
+
+impl BenchmarkStats {
+    /// compare bar returns on the timestamps both runs share, `None` if they share fewer than two
+    pub fn compare(strategy: &Account, benchmark: &Account, risk_free_rate: f64) -> Option<BenchmarkStats> {
+        let strategy_growth = growth_index(strategy);
+        let benchmark_returns: HashMap<_, _> = bar_returns(&growth_index(benchmark)).into_iter().collect();
+        let (strategy_returns, benchmark_returns): (Vec<f64>, Vec<f64>) = bar_returns(&strategy_growth)
+            .into_iter()
+            .filter_map(|(timestamp, r)| Some((r, *benchmark_returns.get(&timestamp)?)))
+            .unzip();
+        if strategy_returns.len() < 2 {
+            return None;
+        }
+        let periods = sampling_periods(&strategy_growth);
+        Some(Self::from_returns(&strategy_returns, &benchmark_returns, periods, risk_free_rate))
+    }
+
+    /// from aligned per-bar returns sampled `periods` times a year
+    pub fn from_returns(strategy: &[f64], benchmark: &[f64], periods: f64, risk_free_rate: f64) -> BenchmarkStats {
+        let risk_free = (1. + risk_free_rate).powf(1. / periods) - 1.;
+        let benchmark_variance = covariance(benchmark, benchmark);
+        let strategy_variance = covariance(strategy, strategy);
+        let shared_variance = covariance(strategy, benchmark);
+        let beta = if benchmark_variance > 0. {
+            shared_variance / benchmark_variance
+        } else {
+            0.
+        };
+        let alpha = ((mean(strategy) - risk_free) - beta * (mean(benchmark) - risk_free)) * periods;
+
+        let active: Vec<f64> = strategy.iter().zip(benchmark).map(|(s, b)| s - b).collect();
+        let tracking_error = covariance(&active, &active).sqrt() * periods.sqrt();
+        let information_ratio = if tracking_error > 1e-12 {
+            Some(mean(&active) * periods / tracking_error)
+        } else {
+            None
+        };
+
+        let pairs = || strategy.iter().copied().zip(benchmark.iter().copied());
+        let correlation = if strategy_variance > 0. && benchmark_variance > 0. {
+            Some(shared_variance / (strategy_variance * benchmark_variance).sqrt())
+        } else {
+            None
+        };
+        BenchmarkStats {
+            alpha,
+            beta,
+            tracking_error,
+            information_ratio,
+            up_capture: capture(pairs().filter(|(_, b)| *b > 0.)),
+            down_capture: capture(pairs().filter(|(_, b)| *b < 0.)),
+            correlation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Position;
+    use chrono::{Duration, NaiveDate};
+
+    fn account(prices: &[f64]) -> Account {
+        let start = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let position = Position {
+            quantity: 1.,
+            cost: prices[0],
+        };
+        let mut account = Account::new(0., position, start);
+        for (i, price) in prices.iter().enumerate().skip(1) {
+            account.mark_to_market(start + Duration::days(i as i64), *price);
+        }
+        account
+    }
+
+    #[test]
+    fn test_against_itself() {
+        let prices = [100., 110., 99., 105., 120.];
+        let stats = BenchmarkStats::compare(&account(&prices), &account(&prices), 0.).unwrap();
+        assert!((stats.beta - 1.).abs() < 1e-9);
+        assert!(stats.alpha.abs() < 1e-9);
+        assert!(stats.tracking_error.abs() < 1e-9);
+        assert_eq!(stats.information_ratio, None);
+        assert!((stats.correlation.unwrap() - 1.).abs() < 1e-9);
+        assert!((stats.up_capture.unwrap() - 1.).abs() < 1e-9);
+        assert!((stats.down_capture.unwrap() - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_half_invested() {
+        let strategy = [0.05, -0.05, 0.1, -0.1];
+        let benchmark = [0.1, -0.1, 0.2, -0.2];
+        let stats = BenchmarkStats::from_returns(&strategy, &benchmark, 365., 0.);
+        assert!((stats.beta - 0.5).abs() < 1e-9);
+        assert!(stats.alpha.abs() < 1e-9);
+        assert!((stats.up_capture.unwrap() - 0.5).abs() < 1e-9);
+        assert!((stats.down_capture.unwrap() - 0.5).abs() < 1e-9);
+        assert!((stats.correlation.unwrap() - 1.).abs() < 1e-9);
+        assert!(stats.tracking_error > 0.);
+        assert!(stats.information_ratio.unwrap().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_no_overlap() {
+        assert_eq!(BenchmarkStats::compare(&account(&[100.]), &account(&[100., 110.]), 0.), None);
+    }
+}
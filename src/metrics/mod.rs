@@ -0,0 +1,41 @@
+mod benchmark;
+mod performance;
+mod periods;
+mod ratios;
+mod trades;
+
+pub use benchmark::BenchmarkStats;
+pub use performance::{Drawdown, Performance};
+pub use periods::{rolling_windows, ReturnsTable, RollingWindow, YearReturns};
+pub use ratios::{periods_per_year, RiskRatios};
+pub use trades::{RoundTrip, TradeStats};
+
+use crate::account::Account;
+use chrono::{Duration, NaiveDateTime};
+
+/// value of one unit invested at the start at every bar, with deposits and withdrawals chained out as the account
+/// does for [`Account::time_weighted_return`]
+pub fn growth_index(account: &Account) -> Vec<(NaiveDateTime, f64)> {
+    account
+        .equity_curve()
+        .iter()
+        .map(|point| (point.timestamp, point.growth))
+        .collect()
+}
+
+/// the return of each bar, stamped with the end of the bar
+fn bar_returns(growth: &[(NaiveDateTime, f64)]) -> Vec<(NaiveDateTime, f64)> {
+    growth
+        .windows(2)
+        .map(|pair| (pair[1].0, pair[1].1 / pair[0].1 - 1.))
+        .collect()
+}
+
+/// bars per year, taking the typical gap between points so the opening point does not skew it
+fn sampling_periods(growth: &[(NaiveDateTime, f64)]) -> f64 {
+    let mut gaps: Vec<Duration> = growth.windows(2).map(|pair| pair[1].0 - pair[0].0).collect();
+    gaps.sort();
+    gaps.get(gaps.len() / 2)
+        .filter(|gap| gap.num_seconds() > 0)
+        .map_or(1., |gap| periods_per_year(*gap))
+}
@@ -0,0 +1,199 @@
+use super::growth_index;
+use crate::account::{Account, SECONDS_PER_YEAR};
+use chrono::{Duration, NaiveDateTime};
+
+/// a fall from a peak, recovered once the previous peak is reached again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drawdown {
+    /// fraction lost from the peak to the trough
+    pub depth: f64,
+    pub start: NaiveDateTime,
+    pub trough: NaiveDateTime,
+    pub recovery: Option<NaiveDateTime>,
+}
+
+impl Drawdown {
+    /// from the peak until recovery, or until `end` if it never recovered
+    pub fn duration(&self, end: NaiveDateTime) -> Duration {
+        self.recovery.unwrap_or(end) - self.start
+    }
+
+    /// from the trough back to the previous peak
+    pub fn time_to_recovery(&self) -> Option<Duration> {
+        self.recovery.map(|recovery| recovery - self.trough)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Performance {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// time-weighted, so deposits and withdrawals do not count as return
+    pub total_return: f64,
+    pub cagr: Option<f64>,
+    pub drawdowns: Vec<Drawdown>,
+}
+
+impl Performance {
+    pub fn from_account(account: &Account) -> Performance {
+        Self::from_growth(&growth_index(account)).expect("an account's equity curve starts with its opening point")
+    }
+
+    /// from a growth index starting at 1, such as one stitched together from several runs, none when it is empty
+    pub fn from_growth(growth: &[(NaiveDateTime, f64)]) -> Option<Performance> {
+        let (start, _) = *growth.first()?;
+        let (end, last) = *growth.last()?;
+        let total_return = last - 1.;
+        let years = (end - start).num_seconds() as f64 / SECONDS_PER_YEAR;
+        let cagr = if years > 0. && last > 0. {
+            Some(last.powf(1. / years) - 1.)
+        } else {
+            None
+        };
+        Some(Performance {
+            start,
+            end,
+            total_return,
+            cagr,
+            drawdowns: drawdowns(growth),
+        })
+    }
+
+    pub fn max_drawdown(&self) -> Option<&Drawdown> {
+        self.drawdowns
+            .iter()
+            .max_by(|a, b| a.depth.total_cmp(&b.depth))
+    }
+
+    pub fn longest_drawdown(&self) -> Option<Duration> {
+        self.drawdowns.iter().map(|drawdown| drawdown.duration(self.end)).max()
+    }
+
+    /// how long the deepest drawdown took to climb back from its trough
+    pub fn time_to_recovery(&self) -> Option<Duration> {
+        self.max_drawdown()?.time_to_recovery()
+    }
+}
+
+fn drawdowns(growth: &[(NaiveDateTime, f64)]) -> Vec<Drawdown> {
+    let mut drawdowns = Vec::new();
+    let (mut peak_time, mut peak) = growth[0];
+    let mut current: Option<Drawdown> = None;
+    for &(timestamp, value) in growth.iter().skip(1) {
+        if value >= peak {
+            if let Some(mut drawdown) = current.take() {
+                drawdown.recovery = Some(timestamp);
+                drawdowns.push(drawdown);
+            }
+            peak = value;
+            peak_time = timestamp;
+            continue;
+        }
+        let depth = 1. - value / peak;
+        let drawdown = current.get_or_insert(Drawdown {
+            depth,
+            start: peak_time,
+            trough: timestamp,
+            recovery: None,
+        });
+        if depth > drawdown.depth {
+            drawdown.depth = depth;
+            drawdown.trough = timestamp;
+        }
+    }
+    drawdowns.extend(current);
+    drawdowns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Position;
+    use crate::metrics::RiskRatios;
+    use chrono::NaiveDate;
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0)
+    }
+
+    /// one unit of the asset and no cash, marked once a day
+    fn account(prices: &[f64]) -> Account {
+        let position = Position {
+            quantity: 1.,
+            cost: prices[0],
+        };
+        let mut account = Account::new(0., position, start());
+        for (i, price) in prices.iter().enumerate().skip(1) {
+            account.mark_to_market(start() + Duration::days(i as i64), *price);
+        }
+        account
+    }
+
+    #[test]
+    fn test_total_return_and_cagr() {
+        let mut account = account(&[100., 110.]);
+        account.mark_to_market(start() + Duration::days(730), 121.);
+        let performance = Performance::from_account(&account);
+        assert!((performance.total_return - 0.21).abs() < 1e-9);
+        assert!((performance.cagr.unwrap() - 0.1).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_max_drawdown() {
+        let performance = Performance::from_account(&account(&[100., 120., 90., 60., 100., 130., 117.]));
+        assert_eq!(performance.drawdowns.len(), 2);
+        let max_drawdown = performance.max_drawdown().unwrap();
+        assert!((max_drawdown.depth - 0.5).abs() < 1e-9);
+        assert_eq!(max_drawdown.start, start() + Duration::days(1));
+        assert_eq!(max_drawdown.trough, start() + Duration::days(3));
+        assert_eq!(max_drawdown.recovery, Some(start() + Duration::days(5)));
+        assert_eq!(performance.time_to_recovery(), Some(Duration::days(2)));
+        assert_eq!(performance.longest_drawdown(), Some(Duration::days(4)));
+    }
+
+    #[test]
+    fn test_unrecovered_drawdown() {
+        let performance = Performance::from_account(&account(&[100., 80., 90.]));
+        let max_drawdown = performance.max_drawdown().unwrap();
+        assert_eq!(max_drawdown.recovery, None);
+        assert_eq!(performance.time_to_recovery(), None);
+        assert_eq!(performance.longest_drawdown(), Some(Duration::days(2)));
+    }
+
+    #[test]
+    fn test_nan_drawdown() {
+        let drawdown = |depth| Drawdown {
+            depth,
+            start: start(),
+            trough: start(),
+            recovery: None,
+        };
+        let performance = Performance {
+            start: start(),
+            end: start(),
+            total_return: 0.,
+            cagr: None,
+            drawdowns: vec![drawdown(0.2), drawdown(f64::NAN), drawdown(0.1)],
+        };
+        assert!(performance.max_drawdown().is_some());
+    }
+
+    #[test]
+    fn test_empty_growth() {
+        assert!(Performance::from_growth(&[]).is_none());
+        assert!(RiskRatios::from_growth(&[], 0.).is_none());
+        let performance = Performance::from_growth(&[(start(), 1.)]).unwrap();
+        assert_eq!(performance.total_return, 0.);
+        assert_eq!(performance.cagr, None);
+    }
+
+    #[test]
+    fn test_deposits_are_not_return() {
+        let mut account = account(&[100., 100.]);
+        account.deposit(start() + Duration::days(2), 100.);
+        account.mark_to_market(start() + Duration::days(2), 100.);
+        let performance = Performance::from_account(&account);
+        assert_eq!(performance.total_return, 0.);
+        assert!(performance.drawdowns.is_empty());
+    }
+}
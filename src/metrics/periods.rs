@@ -0,0 +1,190 @@
+use super::{growth_index, sampling_periods};
+use crate::account::Account;
+use chrono::{Datelike, Duration, NaiveDateTime};
+
+/// one row of the returns heatmap
+#[derive(Debug, Clone, PartialEq)]
+pub struct YearReturns {
+    pub year: i32,
+    /// indexed by month from January, `None` for months outside the backtest
+    pub months: [Option<f64>; 12],
+    pub total: f64,
+}
+
+/// monthly and calendar-year returns, each chained from the last value of the period before
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnsTable {
+    pub years: Vec<YearReturns>,
+}
+
+impl ReturnsTable {
+    pub fn from_account(account: &Account) -> ReturnsTable {
+        let growth = growth_index(account);
+        let mut years: Vec<YearReturns> = Vec::new();
+        let (mut month_start, mut year_start) = (growth[0].1, growth[0].1);
+        for (i, &(timestamp, value)) in growth.iter().enumerate().skip(1) {
+            let last_of_month = growth
+                .get(i + 1)
+                .is_none_or(|(next, _)| (next.year(), next.month()) != (timestamp.year(), timestamp.month()));
+            if !last_of_month {
+                continue;
+            }
+            if years.last().is_none_or(|row| row.year != timestamp.year()) {
+                years.push(YearReturns {
+                    year: timestamp.year(),
+                    months: [None; 12],
+                    total: 0.,
+                });
+            }
+            let row = years.last_mut().unwrap();
+            row.months[timestamp.month0() as usize] = Some(value / month_start - 1.);
+            row.total = value / year_start - 1.;
+            month_start = value;
+            if growth.get(i + 1).is_none_or(|(next, _)| next.year() != timestamp.year()) {
+                year_start = value;
+            }
+        }
+        ReturnsTable { years }
+    }
+}
+
+/// return, volatility and Sharpe over the window ending at `timestamp`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RollingWindow {
+    pub timestamp: NaiveDateTime,
+    pub return_pct: f64,
+    /// annualised
+    pub volatility: f64,
+    pub sharpe: Option<f64>,
+}
+
+/// every full `window` of the backtest, one per bar once the first window has passed, none for an empty window
+pub fn rolling_windows(account: &Account, window: Duration, risk_free_rate: f64) -> Vec<RollingWindow> {
+    if window <= Duration::zero() {
+        return Vec::new();
+    }
+    let growth = growth_index(account);
+    let periods = sampling_periods(&growth);
+    let risk_free = (1. + risk_free_rate).powf(1. / periods) - 1.;
+
+    // prefix sums of bar returns and their squares, so each window is constant time
+    let mut sums = vec![(0., 0.)];
+    for pair in growth.windows(2) {
+        let r = pair[1].1 / pair[0].1 - 1.;
+        let (sum, squares) = *sums.last().unwrap();
+        sums.push((sum + r, squares + r * r));
+    }
+
+    let mut windows = Vec::new();
+    let mut start = 0;
+    for (end, &(timestamp, value)) in growth.iter().enumerate() {
+        if timestamp - growth[0].0 < window {
+            continue;
+        }
+        // keep at least one bar in the window however far apart the bars are
+        while start + 1 < end && growth[start + 1].0 <= timestamp - window {
+            start += 1;
+        }
+        let count = (end - start) as f64;
+        let mean = (sums[end].0 - sums[start].0) / count;
+        let squares = sums[end].1 - sums[start].1;
+        let variance = ((squares - count * mean * mean) / (count - 1.).max(1.)).max(0.);
+        let volatility = variance.sqrt() * periods.sqrt();
+        // the prefix sums leave rounding noise well above zero for constant returns
+        let sharpe = if volatility > 1e-6 {
+            Some((mean - risk_free) * periods / volatility)
+        } else {
+            None
+        };
+        windows.push(RollingWindow {
+            timestamp,
+            return_pct: value / growth[start].1 - 1.,
+            volatility,
+            sharpe,
+        });
+    }
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Position;
+    use chrono::NaiveDate;
+
+    fn account(start: NaiveDateTime, marks: &[(NaiveDateTime, f64)]) -> Account {
+        let position = Position { quantity: 1., cost: 100. };
+        let mut account = Account::new(0., position, start);
+        for (timestamp, price) in marks.iter() {
+            account.mark_to_market(*timestamp, *price);
+        }
+        account
+    }
+
+    fn daily(prices: &[f64]) -> Account {
+        let start = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let marks: Vec<_> = prices
+            .iter()
+            .enumerate()
+            .map(|(i, price)| (start + Duration::days(i as i64 + 1), *price))
+            .collect();
+        account(start, &marks)
+    }
+
+    #[test]
+    fn test_returns_table() {
+        let date = |y, m, d| NaiveDate::from_ymd(y, m, d).and_hms(0, 0, 0);
+        let marks = [
+            (date(2021, 11, 15), 95.),
+            (date(2021, 11, 30), 100.),
+            (date(2021, 12, 15), 105.),
+            (date(2021, 12, 31), 110.),
+            (date(2022, 1, 15), 115.),
+            (date(2022, 1, 31), 121.),
+            (date(2022, 2, 15), 100.),
+            (date(2022, 2, 28), 108.9),
+        ];
+        let table = ReturnsTable::from_account(&account(date(2021, 11, 1), &marks));
+        assert_eq!(table.years.len(), 2);
+        assert_eq!(table.years[0].year, 2021);
+        assert!(table.years[0].months[9].is_none());
+        assert!(table.years[0].months[10].unwrap().abs() < 1e-9);
+        assert!((table.years[0].months[11].unwrap() - 0.1).abs() < 1e-9);
+        assert!((table.years[0].total - 0.1).abs() < 1e-9);
+        assert!((table.years[1].months[0].unwrap() - 0.1).abs() < 1e-9);
+        assert!((table.years[1].months[1].unwrap() + 0.1).abs() < 1e-9);
+        assert!((table.years[1].total + 0.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rolling_windows() {
+        let windows = rolling_windows(&daily(&[110., 121., 133.1, 146.41]), Duration::days(2), 0.);
+        assert_eq!(windows.len(), 3);
+        assert_eq!(windows[0].timestamp, NaiveDate::from_ymd(2021, 1, 3).and_hms(0, 0, 0));
+        for window in windows.iter() {
+            assert!((window.return_pct - 0.21).abs() < 1e-9);
+            assert!(window.volatility < 1e-6);
+            assert_eq!(window.sharpe, None);
+        }
+    }
+
+    #[test]
+    fn test_rolling_window_edges() {
+        let account = daily(&[110., 121., 133.1]);
+        assert!(rolling_windows(&account, Duration::zero(), 0.).is_empty());
+        assert!(rolling_windows(&account, Duration::days(-1), 0.).is_empty());
+        // shorter than a bar, so every window holds just the latest bar
+        let windows = rolling_windows(&account, Duration::hours(1), 0.);
+        assert_eq!(windows.len(), 3);
+        assert!(windows.iter().all(|window| (window.return_pct - 0.1).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_rolling_volatility() {
+        let windows = rolling_windows(&daily(&[110., 99., 108.9]), Duration::days(3), 0.);
+        assert_eq!(windows.len(), 1);
+        // returns of 10%, -10% and 10% deviate from their mean by 2/30, 4/30 and 2/30
+        let expected = (1f64 / 75.).sqrt() * 365f64.sqrt();
+        assert!((windows[0].volatility - expected).abs() < 1e-9);
+    }
+}
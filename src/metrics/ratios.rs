@@ -0,0 +1,127 @@
+use super::{bar_returns, growth_index, sampling_periods, Performance};
+use crate::account::Account;
+use chrono::{Duration, NaiveDateTime};
+
+/// the 365 day year volatility is annualised over, CAGR and yield keep compounding over 365.25 days
+const SECONDS_PER_TRADING_YEAR: f64 = 365. * 24. * 60. * 60.;
+
+/// how many bars of `interval` make up a year, crypto trades around the clock so 8760 for hourly klines
+pub fn periods_per_year(interval: Duration) -> f64 {
+    SECONDS_PER_TRADING_YEAR / interval.num_seconds() as f64
+}
+
+/// annualised ratios, `None` where the denominator is zero
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiskRatios {
+    pub volatility: f64,
+    pub sharpe: Option<f64>,
+    pub sortino: Option<f64>,
+    pub calmar: Option<f64>,
+    pub omega: Option<f64>,
+}
+
+fn ratio(numerator: f64, denominator: f64) -> Option<f64> {
+    // constant returns leave rounding noise rather than an exact zero
+    if denominator > 1e-12 {
+        Some(numerator / denominator)
+    } else {
+        None
+    }
+}
+
+impl RiskRatios {
+    /// `risk_free_rate` is an annual rate, the threshold for excess returns
+    pub fn from_account(account: &Account, risk_free_rate: f64) -> RiskRatios {
+        Self::from_growth(&growth_index(account), risk_free_rate)
+            .expect("an account's equity curve starts with its opening point")
+    }
+
+    /// from a growth index starting at 1, none when it is empty
+    pub fn from_growth(growth: &[(NaiveDateTime, f64)], risk_free_rate: f64) -> Option<RiskRatios> {
+        let performance = Performance::from_growth(growth)?;
+        let returns: Vec<f64> = bar_returns(growth).into_iter().map(|(_, r)| r).collect();
+        let periods = sampling_periods(growth);
+        let max_drawdown = performance.max_drawdown().map_or(0., |drawdown| drawdown.depth);
+        Some(Self::from_returns(&returns, periods, risk_free_rate, performance.cagr, max_drawdown))
+    }
+
+    /// from per-bar returns sampled `periods` times a year
+    pub fn from_returns(
+        returns: &[f64],
+        periods: f64,
+        risk_free_rate: f64,
+        cagr: Option<f64>,
+        max_drawdown: f64,
+    ) -> RiskRatios {
+        let threshold = (1. + risk_free_rate).powf(1. / periods) - 1.;
+        let count = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / count.max(1.);
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (count - 1.).max(1.);
+        let downside = returns.iter().map(|r| (r - threshold).min(0.).powi(2)).sum::<f64>() / count.max(1.);
+        let gains: f64 = returns.iter().map(|r| (r - threshold).max(0.)).sum();
+        let losses: f64 = returns.iter().map(|r| (threshold - r).max(0.)).sum();
+
+        let annualise = periods.sqrt();
+        RiskRatios {
+            volatility: variance.sqrt() * annualise,
+            sharpe: ratio(mean - threshold, variance.sqrt()).map(|sharpe| sharpe * annualise),
+            sortino: ratio(mean - threshold, downside.sqrt()).map(|sortino| sortino * annualise),
+            calmar: cagr.and_then(|cagr| ratio(cagr, max_drawdown)),
+            omega: ratio(gains, losses),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Position;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_periods_per_year() {
+        assert_eq!(periods_per_year(Duration::hours(1)), 8760.);
+        assert_eq!(periods_per_year(Duration::days(1)), 365.);
+    }
+
+    #[test]
+    fn test_from_returns() {
+        let returns = [0.01, -0.01, 0.02, -0.02];
+        let ratios = RiskRatios::from_returns(&returns, 365., 0., Some(0.3), 0.15);
+        assert!((ratios.volatility - (0.001 / 3f64).sqrt() * 365f64.sqrt()).abs() < 1e-12);
+        assert_eq!(ratios.sharpe, Some(0.));
+        assert_eq!(ratios.sortino, Some(0.));
+        assert!((ratios.calmar.unwrap() - 2.).abs() < 1e-12);
+        assert!((ratios.omega.unwrap() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_risk_free_rate() {
+        let returns = [0.001; 10];
+        let ratios = RiskRatios::from_returns(&returns, 8760., 0.05, None, 0.);
+        assert_eq!(ratios.sharpe, None);
+        assert!(ratios.sortino.is_none());
+        assert_eq!(ratios.calmar, None);
+        // every bar beats the hourly equivalent of 5% a year
+        assert_eq!(ratios.omega, None);
+
+        let ratios = RiskRatios::from_returns(&[0.001, -0.001], 8760., 0.05, None, 0.);
+        assert!(ratios.sharpe.unwrap() < 0.);
+    }
+
+    #[test]
+    fn test_from_account() {
+        let start = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let position = Position { quantity: 1., cost: 100. };
+        let mut account = Account::new(0., position, start);
+        for (i, price) in [101., 100., 102., 101.].iter().enumerate() {
+            account.mark_to_market(start + Duration::hours(i as i64 + 1), *price);
+        }
+        let ratios = RiskRatios::from_account(&account, 0.);
+        let returns = [0.01, 100. / 101. - 1., 0.02, 101. / 102. - 1.];
+        let cagr = Performance::from_account(&account).cagr;
+        let expected = RiskRatios::from_returns(&returns, 8760., 0., cagr, 1. - 100. / 101.);
+        assert!((ratios.volatility - expected.volatility).abs() < 1e-9);
+        assert!((ratios.sharpe.unwrap() - expected.sharpe.unwrap()).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::account::{Account, BuySellIndicator, Trade};
+use crate::data::BinanceKline;
+use chrono::{Duration, NaiveDateTime};
+
+/// a position opened from flat and closed back to flat, however many fills it took
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoundTrip {
+    pub entry: NaiveDateTime,
+    pub exit: NaiveDateTime,
+    /// average price paid per unit received
+    pub entry_price: f64,
+    /// proceeds less cost and fees, in the quote currency
+    pub pnl: f64,
+    pub return_pct: f64,
+    /// max adverse excursion, the worst low while held as a fraction below the entry price
+    pub mae: f64,
+    /// max favourable excursion, the best high while held as a fraction above the entry price
+    pub mfe: f64,
+}
+
+impl RoundTrip {
+    pub fn holding_time(&self) -> Duration {
+        self.exit - self.entry
+    }
+
+    pub fn is_win(&self) -> bool {
+        self.pnl > 0.
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeStats {
+    pub round_trips: Vec<RoundTrip>,
+}
+
+#[derive(Default)]
+struct OpenTrip {
+    entry: Option<NaiveDateTime>,
+    held: f64,
+    bought: f64,
+    cost: f64,
+    proceeds: f64,
+    fees: f64,
+}
+
+impl OpenTrip {
+    fn apply(&mut self, trade: &Trade) {
+        self.entry.get_or_insert(trade.timestamp());
+        let notional = trade.quantity() * trade.price();
+        match trade.buy_sell_indicator() {
+            BuySellIndicator::Buy => {
+                self.held += trade.received_quantity();
+                self.bought += trade.received_quantity();
+                self.cost += notional;
+            }
+            BuySellIndicator::Sell => {
+                self.held -= trade.quantity();
+                self.proceeds += notional;
+            }
+        }
+        self.fees += trade.fee_cost();
+    }
+
+    /// a position held from the start, taken on at its cost when the account opened
+    fn starting(account: &Account) -> Self {
+        match account.equity_curve().first() {
+            Some(start) if start.quantity > 0. => OpenTrip {
+                entry: Some(start.timestamp),
+                held: start.quantity,
+                bought: start.quantity,
+                cost: start.quantity * start.price,
+                ..OpenTrip::default()
+            },
+            _ => OpenTrip::default(),
+        }
+    }
+
+    /// whatever is left is float noise or dust too small to sell
+    fn is_flat(&self, dust: f64) -> bool {
+        self.held <= (self.bought * 1e-9).max(dust)
+    }
+}
+
+fn excursions(klines: &[BinanceKline], entry: NaiveDateTime, exit: NaiveDateTime, entry_price: f64) -> (f64, f64) {
+    let held = klines
+        .iter()
+        .filter(|kline| kline.end_time > entry && kline.start_time < exit);
+    let (low, high) = held.fold((entry_price, entry_price), |(low, high), kline| {
+        (low.min(kline.low), high.max(kline.high))
+    });
+    (1. - low / entry_price, high / entry_price - 1.)
+}
+
+impl TradeStats {
+    /// pair up the fills in the trade history, `klines` are only needed for MAE and MFE
+    pub fn from_account(account: &Account, klines: &[BinanceKline]) -> TradeStats {
+        let mut round_trips = Vec::new();
+        let mut open = OpenTrip::starting(account);
+        for trade in account.trade_history.iter() {
+            open.apply(trade);
+            if trade.buy_sell_indicator() == BuySellIndicator::Sell && open.bought <= 0. {
+                // nothing was bought or held to sell, there is no trip to record
+                open = OpenTrip::default();
+                continue;
+            }
+            if trade.buy_sell_indicator() == BuySellIndicator::Sell && open.is_flat(account.dust) {
+                let entry = open.entry.unwrap();
+                let exit = trade.timestamp();
+                let entry_price = open.cost / open.bought;
+                let pnl = open.proceeds - open.cost - open.fees;
+                let (mae, mfe) = excursions(klines, entry, exit, entry_price);
+                round_trips.push(RoundTrip {
+                    entry,
+                    exit,
+                    entry_price,
+                    pnl,
+                    return_pct: if open.cost > 0. { pnl / open.cost } else { 0. },
+                    mae,
+                    mfe,
+                });
+                open = OpenTrip::default();
+            }
+        }
+        TradeStats { round_trips }
+    }
+
+    pub fn count(&self) -> usize {
+        self.round_trips.len()
+    }
+
+    fn wins(&self) -> impl Iterator<Item = &RoundTrip> {
+        self.round_trips.iter().filter(|trip| trip.is_win())
+    }
+
+    fn losses(&self) -> impl Iterator<Item = &RoundTrip> {
+        self.round_trips.iter().filter(|trip| !trip.is_win())
+    }
+
+    pub fn win_rate(&self) -> Option<f64> {
+        match self.count() {
+            0 => None,
+            count => Some(self.wins().count() as f64 / count as f64),
+        }
+    }
+
+    pub fn average_win(&self) -> Option<f64> {
+        average(self.wins().map(|trip| trip.pnl))
+    }
+
+    /// as a negative amount
+    pub fn average_loss(&self) -> Option<f64> {
+        average(self.losses().map(|trip| trip.pnl))
+    }
+
+    /// gross profit over gross loss, `None` without any losing trade
+    pub fn profit_factor(&self) -> Option<f64> {
+        let gross_loss: f64 = -self.losses().map(|trip| trip.pnl).sum::<f64>();
+        if gross_loss > 0. {
+            Some(self.wins().map(|trip| trip.pnl).sum::<f64>() / gross_loss)
+        } else {
+            None
+        }
+    }
+
+    /// the average pnl per round trip
+    pub fn expectancy(&self) -> Option<f64> {
+        average(self.round_trips.iter().map(|trip| trip.pnl))
+    }
+
+    pub fn longest_winning_streak(&self) -> usize {
+        self.longest_streak(true)
+    }
+
+    pub fn longest_losing_streak(&self) -> usize {
+        self.longest_streak(false)
+    }
+
+    fn longest_streak(&self, win: bool) -> usize {
+        let mut longest = 0;
+        let mut current = 0;
+        for trip in self.round_trips.iter() {
+            current = if trip.is_win() == win { current + 1 } else { 0 };
+            longest = longest.max(current);
+        }
+        longest
+    }
+
+    pub fn average_holding_time(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            count => Some(
+                self.round_trips
+                    .iter()
+                    .map(|trip| trip.holding_time())
+                    .fold(Duration::zero(), |total, holding_time| total + holding_time)
+                    / count as i32,
+            ),
+        }
+    }
+
+    pub fn average_mae(&self) -> Option<f64> {
+        average(self.round_trips.iter().map(|trip| trip.mae))
+    }
+
+    pub fn average_mfe(&self) -> Option<f64> {
+        average(self.round_trips.iter().map(|trip| trip.mfe))
+    }
+}
+
+fn average(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0., 0), |(sum, count), value| (sum + value, count + 1));
+    if count > 0 {
+        Some(sum / count as f64)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::Position;
+    use chrono::NaiveDate;
+
+    fn hour(i: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0) + Duration::hours(i)
+    }
+
+    fn klines(lows_and_highs: &[(f64, f64)]) -> Vec<BinanceKline> {
+        lows_and_highs
+            .iter()
+            .enumerate()
+            .map(|(i, (low, high))| BinanceKline {
+                start_time: hour(i as i64),
+                open: *low,
+                high: *high,
+                low: *low,
+                close: *high,
+                volume: 1000.,
+                end_time: hour(i as i64 + 1) - Duration::seconds(1),
+            })
+            .collect()
+    }
+
+    fn account() -> Account {
+        let position = Position { quantity: 0., cost: 0. };
+        let mut account = Account::new(10000., position, hour(0));
+        // a win in two fills, a loss, a win, then a position left open
+        account.open(hour(0), 2., 100., 1., 0.);
+        account.close(hour(2), 1., 110., 0., 0.);
+        account.close(hour(3), 1., 120., 1., 0.);
+        account.open(hour(4), 1., 100., 0., 0.);
+        account.close(hour(5), 1., 90., 0., 0.);
+        account.open(hour(6), 1., 100., 0., 0.);
+        account.close(hour(7), 1., 105., 0., 0.);
+        account.open(hour(8), 1., 100., 0., 0.);
+        account
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let stats = TradeStats::from_account(&account(), &[]);
+        assert_eq!(stats.count(), 3);
+        let first = stats.round_trips[0];
+        assert_eq!(first.entry, hour(0));
+        assert_eq!(first.exit, hour(3));
+        assert_eq!(first.pnl, 28.);
+        assert_eq!(first.return_pct, 0.14);
+        assert_eq!(first.holding_time(), Duration::hours(3));
+        assert_eq!(stats.round_trips[1].pnl, -10.);
+    }
+
+    #[test]
+    fn test_summary() {
+        let stats = TradeStats::from_account(&account(), &[]);
+        assert_eq!(stats.win_rate(), Some(2. / 3.));
+        assert_eq!(stats.average_win(), Some(16.5));
+        assert_eq!(stats.average_loss(), Some(-10.));
+        assert_eq!(stats.profit_factor(), Some(3.3));
+        assert_eq!(stats.expectancy(), Some(23. / 3.));
+        assert_eq!(stats.longest_winning_streak(), 1);
+        assert_eq!(stats.longest_losing_streak(), 1);
+        assert_eq!(stats.average_holding_time(), Some(Duration::minutes(100)));
+    }
+
+    #[test]
+    fn test_excursions() {
+        let klines = klines(&[(95., 101.), (90., 104.), (99., 130.), (100., 120.)]);
+        let stats = TradeStats::from_account(&account(), &klines);
+        let first = stats.round_trips[0];
+        assert!((first.mae - 0.1).abs() < 1e-9);
+        assert!((first.mfe - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_no_trades() {
+        let position = Position { quantity: 0., cost: 0. };
+        let stats = TradeStats::from_account(&Account::new(1000., position, hour(0)), &[]);
+        assert_eq!(stats.win_rate(), None);
+        assert_eq!(stats.profit_factor(), None);
+        assert_eq!(stats.average_holding_time(), None);
+    }
+
+    #[test]
+    fn test_dust_closes_trip() {
+        let position = Position { quantity: 0., cost: 0. };
+        let mut account = Account::new(10000., position, hour(0));
+        // each sale is rounded down to a 0.001 lot, leaving dust behind
+        account.open(hour(0), 1.2345, 100., 0., 0.);
+        account.close(hour(1), 1.234, 110., 0., 0.);
+        account.open(hour(2), 1.0004, 100., 0., 0.);
+        account.close(hour(3), 1., 90., 0., 0.);
+        // without the lot rules the dust keeps one trip open forever
+        assert_eq!(TradeStats::from_account(&account, &[]).count(), 0);
+
+        account.dust = 0.001;
+        let stats = TradeStats::from_account(&account, &[]);
+        assert_eq!(stats.count(), 2);
+        assert!(stats.round_trips[0].is_win());
+        assert!(!stats.round_trips[1].is_win());
+        assert_eq!(stats.round_trips[1].entry, hour(2));
+    }
+
+    #[test]
+    fn test_starting_position() {
+        let position = Position { quantity: 2., cost: 100. };
+        let mut account = Account::new(0., position, hour(0));
+        account.close(hour(2), 2., 120., 0., 0.);
+        let stats = TradeStats::from_account(&account, &[]);
+        assert_eq!(stats.count(), 1);
+        let trip = stats.round_trips[0];
+        assert_eq!(trip.entry, hour(0));
+        assert_eq!(trip.entry_price, 100.);
+        assert_eq!(trip.pnl, 40.);
+        assert_eq!(trip.return_pct, 0.2);
+    }
+}
@@ -0,0 +1,277 @@
+use crate::data::BinanceKline;
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderType {
+    Market,
+    Limit(f64),
+    StopMarket(f64),
+    StopLimit { stop: f64, limit: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Order {
+    pub id: usize,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub quantity: f64,
+    pub created_at: NaiveDateTime,
+    pub expires_at: Option<NaiveDateTime>,
+    triggered: bool,
+}
+
+impl Order {
+    pub fn is_market_fill(&self) -> bool {
+        matches!(self.order_type, OrderType::Market | OrderType::StopMarket(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fill {
+    pub order: Order,
+    pub price: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct OrderBook {
+    orders: Vec<Order>,
+    next_id: usize,
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn submit(
+        &mut self,
+        side: OrderSide,
+        order_type: OrderType,
+        quantity: f64,
+        created_at: NaiveDateTime,
+        expires_at: Option<NaiveDateTime>,
+    ) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.orders.push(Order {
+            id,
+            side,
+            order_type,
+            quantity,
+            created_at,
+            expires_at,
+            triggered: false,
+        });
+        id
+    }
+
+    pub fn open_orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    pub fn cancel(&mut self, id: usize) -> Option<Order> {
+        let index = self.orders.iter().position(|order| order.id == id)?;
+        Some(self.orders.remove(index))
+    }
+
+    pub fn cancel_all(&mut self) {
+        self.orders.clear();
+    }
+
+    /// cancel part of an order, removing it once nothing is left
+    pub fn reduce(&mut self, id: usize, quantity: f64) -> Option<Order> {
+        let order = self.orders.iter_mut().find(|order| order.id == id)?;
+        order.quantity -= quantity;
+        let order = *order;
+        if order.quantity <= 0. {
+            self.cancel(id);
+        }
+        Some(order)
+    }
+
+    pub fn expire(&mut self, now: NaiveDateTime) -> Vec<Order> {
+        let (expired, alive) = self
+            .orders
+            .drain(..)
+            .partition(|order| order.expires_at.is_some_and(|expiry| expiry <= now));
+        self.orders = alive;
+        expired
+    }
+
+    /// remove and return every order whose trigger is crossed by the candle
+    pub fn match_kline(&mut self, kline: &BinanceKline) -> Vec<Fill> {
+        let mut fills = Vec::new();
+        let mut resting = Vec::new();
+        for mut order in self.orders.drain(..) {
+            match fill_price(&mut order, kline) {
+                Some(price) => fills.push(Fill { order, price }),
+                None => resting.push(order),
+            }
+        }
+        self.orders = resting;
+        fills
+    }
+}
+
+/// fills when the price reaches the limit somewhere between `low` and `high`, at `reference` if that is better
+fn limit_price(side: OrderSide, limit: f64, reference: f64, low: f64, high: f64) -> Option<f64> {
+    match side {
+        OrderSide::Buy if low <= limit => Some(reference.min(limit)),
+        OrderSide::Sell if high >= limit => Some(reference.max(limit)),
+        _ => None,
+    }
+}
+
+fn stop_price(side: OrderSide, stop: f64, kline: &BinanceKline) -> Option<f64> {
+    match side {
+        OrderSide::Buy if kline.high >= stop => Some(kline.open.max(stop)),
+        OrderSide::Sell if kline.low <= stop => Some(kline.open.min(stop)),
+        _ => None,
+    }
+}
+
+fn fill_price(order: &mut Order, kline: &BinanceKline) -> Option<f64> {
+    match order.order_type {
+        OrderType::Market => Some(kline.open),
+        OrderType::Limit(limit) => limit_price(order.side, limit, kline.open, kline.low, kline.high),
+        OrderType::StopMarket(stop) => stop_price(order.side, stop, kline),
+        OrderType::StopLimit { stop, limit } => {
+            if order.triggered {
+                return limit_price(order.side, limit, kline.open, kline.low, kline.high);
+            }
+            let triggered_at = stop_price(order.side, stop, kline)?;
+            order.triggered = true;
+            // the rest of the bar can only be known to run from the stop to the close, a limit reached before
+            // the stop waits for the next bar
+            let (low, high) = (triggered_at.min(kline.close), triggered_at.max(kline.close));
+            limit_price(order.side, limit, triggered_at, low, high)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate};
+
+    fn timestamp() -> NaiveDateTime {
+        NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0)
+    }
+
+    fn kline(open: f64, high: f64, low: f64, close: f64) -> BinanceKline {
+        BinanceKline {
+            start_time: timestamp(),
+            open,
+            high,
+            low,
+            close,
+            volume: 100.,
+            end_time: timestamp() + Duration::hours(1),
+        }
+    }
+
+    #[test]
+    fn test_limit_order() {
+        let mut book = OrderBook::new();
+        book.submit(OrderSide::Buy, OrderType::Limit(95.), 1., timestamp(), None);
+        book.submit(OrderSide::Sell, OrderType::Limit(110.), 1., timestamp(), None);
+
+        let fills = book.match_kline(&kline(100., 105., 96., 100.));
+        assert!(fills.is_empty());
+
+        let fills = book.match_kline(&kline(100., 112., 94., 100.));
+        assert_eq!(fills.len(), 2);
+        assert_eq!(fills[0].price, 95.);
+        assert_eq!(fills[1].price, 110.);
+        assert!(book.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_limit_order_gap() {
+        let mut book = OrderBook::new();
+        book.submit(OrderSide::Buy, OrderType::Limit(95.), 1., timestamp(), None);
+        let fills = book.match_kline(&kline(90., 92., 88., 91.));
+        assert_eq!(fills[0].price, 90.);
+    }
+
+    #[test]
+    fn test_stop_market_order() {
+        let mut book = OrderBook::new();
+        book.submit(OrderSide::Sell, OrderType::StopMarket(90.), 1., timestamp(), None);
+        assert!(book.match_kline(&kline(100., 105., 91., 100.)).is_empty());
+        let fills = book.match_kline(&kline(100., 101., 85., 86.));
+        assert_eq!(fills[0].price, 90.);
+        assert!(fills[0].order.is_market_fill());
+    }
+
+    #[test]
+    fn test_stop_limit_order() {
+        let mut book = OrderBook::new();
+        let order_type = OrderType::StopLimit { stop: 105., limit: 103. };
+        book.submit(OrderSide::Buy, order_type, 1., timestamp(), None);
+
+        // stop triggers above the limit, so the order rests as a limit order
+        assert!(book.match_kline(&kline(104., 106., 104., 106.)).is_empty());
+        assert_eq!(book.open_orders().len(), 1);
+
+        let fills = book.match_kline(&kline(106., 107., 102., 103.));
+        assert_eq!(fills[0].price, 103.);
+        assert!(!fills[0].order.is_market_fill());
+    }
+
+    #[test]
+    fn test_stop_limit_trigger_bar() {
+        let order_type = OrderType::StopLimit { stop: 105., limit: 103. };
+
+        // the bar dips to the limit before rising through the stop, too early to fill
+        let mut book = OrderBook::new();
+        book.submit(OrderSide::Buy, order_type, 1., timestamp(), None);
+        assert!(book.match_kline(&kline(104., 106., 102., 106.)).is_empty());
+        assert_eq!(book.match_kline(&kline(104., 105., 102., 104.))[0].price, 103.);
+
+        // after the stop the bar falls back through the limit into its close
+        let mut book = OrderBook::new();
+        book.submit(OrderSide::Buy, order_type, 1., timestamp(), None);
+        assert_eq!(book.match_kline(&kline(104., 106., 102., 102.5))[0].price, 103.);
+
+        let mut book = OrderBook::new();
+        let order_type = OrderType::StopLimit { stop: 95., limit: 96. };
+        book.submit(OrderSide::Sell, order_type, 1., timestamp(), None);
+        assert!(book.match_kline(&kline(97., 98., 94., 94.5)).is_empty());
+        assert_eq!(book.match_kline(&kline(95., 97., 94., 96.))[0].price, 96.);
+    }
+
+    #[test]
+    fn test_cancel_and_reduce() {
+        let mut book = OrderBook::new();
+        let first = book.submit(OrderSide::Buy, OrderType::Limit(95.), 2., timestamp(), None);
+        let second = book.submit(OrderSide::Buy, OrderType::Limit(90.), 1., timestamp(), None);
+
+        assert_eq!(book.reduce(first, 0.5).unwrap().quantity, 1.5);
+        assert_eq!(book.open_orders()[0].quantity, 1.5);
+        book.reduce(first, 1.5);
+        assert_eq!(book.open_orders().len(), 1);
+
+        assert_eq!(book.cancel(second).unwrap().id, second);
+        assert!(book.cancel(second).is_none());
+    }
+
+    #[test]
+    fn test_expire() {
+        let mut book = OrderBook::new();
+        let expiry = Some(timestamp() + Duration::hours(2));
+        book.submit(OrderSide::Buy, OrderType::Limit(95.), 1., timestamp(), expiry);
+        book.submit(OrderSide::Buy, OrderType::Limit(90.), 1., timestamp(), None);
+
+        assert!(book.expire(timestamp() + Duration::hours(1)).is_empty());
+        let expired = book.expire(timestamp() + Duration::hours(2));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(book.open_orders().len(), 1);
+    }
+}
@@ -0,0 +1,430 @@
+use crate::account::Account;
+use crate::data::BinanceKline;
+use crate::metrics::{rolling_windows, BenchmarkStats, Performance, ReturnsTable, RiskRatios, TradeStats};
+use crate::walk_forward::WalkForward;
+use chrono::Duration;
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+use log::{info, warn};
+
+/// one line per run, the headline numbers only
+pub fn log_summary(name: &str, account: &Account, risk_free_rate: f64) {
+    let performance = Performance::from_account(account);
+    let ratios = RiskRatios::from_account(account, risk_free_rate);
+    info!(
+        "{}: equity ${:.02}, contributed ${:.02}, total return {:.02}%, max drawdown {:.02}%, Sharpe {}, {} trades",
+        name,
+        account.equity(),
+        account.net_contribution(),
+        performance.total_return * 100.,
+        performance.max_drawdown().map_or(0., |drawdown| drawdown.depth) * 100.,
+        ratios.sharpe.map_or("n/a".to_string(), |sharpe| format!("{:.02}", sharpe)),
+        account.trade_history.len()
+    );
+    if let Err(errors) = account.audit() {
+        for error in errors {
+            warn!("{}: audit failed, {:?}", name, error);
+        }
+    }
+}
+
+/// every metric for one run, with the audit result
+pub fn log_account(name: &str, account: &Account, klines: &[BinanceKline], risk_free_rate: f64) {
+    let pnl = account.profit_and_loss_history.last().unwrap();
+    info!(
+        "{}: {} pnl ${:.02} (realised ${:.02}, unrealised ${:.02})",
+        name,
+        pnl.timestamp(),
+        pnl.total_pnl(),
+        pnl.realised_pnl(),
+        pnl.unrealised_pnl()
+    );
+    let last = account.equity_curve().last().unwrap();
+    info!(
+        "{}: cash ${:.02}, position {:.04} worth ${:.02}, fees ${:.02}",
+        name, last.cash, last.quantity, last.market_value, last.fees
+    );
+    info!(
+        "{}: equity ${:.02}, contributed ${:.02}, time-weighted return {:.02}%, money-weighted return {:.02}% p.a.",
+        name,
+        account.equity(),
+        account.net_contribution(),
+        account.time_weighted_return() * 100.,
+        account.money_weighted_return().unwrap_or(f64::NAN) * 100.
+    );
+    let performance = Performance::from_account(account);
+    info!(
+        "{}: total return {:.02}%, CAGR {:.02}%, longest drawdown {} days",
+        name,
+        performance.total_return * 100.,
+        performance.cagr.unwrap_or(f64::NAN) * 100.,
+        performance.longest_drawdown().map_or(0, |duration| duration.num_days())
+    );
+    let ratios = RiskRatios::from_account(account, risk_free_rate);
+    let format_ratio = |ratio: Option<f64>| ratio.map_or("n/a".to_string(), |ratio| format!("{:.02}", ratio));
+    info!(
+        "{}: volatility {:.02}%, Sharpe {}, Sortino {}, Calmar {}, Omega {}",
+        name,
+        ratios.volatility * 100.,
+        format_ratio(ratios.sharpe),
+        format_ratio(ratios.sortino),
+        format_ratio(ratios.calmar),
+        format_ratio(ratios.omega)
+    );
+    if let Some(drawdown) = performance.max_drawdown() {
+        info!(
+            "{}: max drawdown {:.02}% from {} to {}, recovered {}",
+            name,
+            drawdown.depth * 100.,
+            drawdown.start,
+            drawdown.trough,
+            drawdown.recovery.map_or("never".to_string(), |recovery| {
+                format!("{} after {} days", recovery, performance.time_to_recovery().unwrap().num_days())
+            })
+        );
+    }
+    for rejection in account.rejections.iter() {
+        info!(
+            "{}: {} {:?} {:.08} @ ${:.08} not filled, {:?}",
+            name,
+            rejection.timestamp(),
+            rejection.buy_sell_indicator(),
+            rejection.quantity(),
+            rejection.price(),
+            rejection.reason()
+        );
+    }
+    let stats = TradeStats::from_account(account, klines);
+    let format_pct = |value: Option<f64>| value.map_or("n/a".to_string(), |value| format!("{:.02}%", value * 100.));
+    info!(
+        "{}: {} round trips, win rate {}, average win ${:.02}, average loss ${:.02}, profit factor {}, expectancy ${:.02}",
+        name,
+        stats.count(),
+        format_pct(stats.win_rate()),
+        stats.average_win().unwrap_or(0.),
+        stats.average_loss().unwrap_or(0.),
+        stats.profit_factor().map_or("n/a".to_string(), |factor| format!("{:.02}", factor)),
+        stats.expectancy().unwrap_or(0.)
+    );
+    info!(
+        "{}: streaks {} won / {} lost, average holding {} hours, average MAE {}, average MFE {}",
+        name,
+        stats.longest_winning_streak(),
+        stats.longest_losing_streak(),
+        stats.average_holding_time().map_or(0, |duration| duration.num_hours()),
+        format_pct(stats.average_mae()),
+        format_pct(stats.average_mfe())
+    );
+    for row in ReturnsTable::from_account(account).years {
+        let months: Vec<String> = row
+            .months
+            .iter()
+            .map(|month| month.map_or("      -".to_string(), |r| format!("{:+6.01}%", r * 100.)))
+            .collect();
+        info!("{}: {} {:+7.02}% | {}", name, row.year, row.total * 100., months.join(" "));
+    }
+    if let Err(errors) = account.audit() {
+        for error in errors {
+            warn!("{}: audit failed, {:?}", name, error);
+        }
+    }
+}
+
+/// how a run did against a benchmark run over the same bars
+pub fn log_benchmark(name: &str, account: &Account, benchmark_name: &str, benchmark: &Account, risk_free_rate: f64) {
+    let stats = match BenchmarkStats::compare(account, benchmark, risk_free_rate) {
+        Some(stats) => stats,
+        None => return,
+    };
+    let format_ratio = |ratio: Option<f64>| ratio.map_or("n/a".to_string(), |ratio| format!("{:.02}", ratio));
+    info!(
+        "{} vs {}: alpha {:.02}%, beta {:.02}, tracking error {:.02}%, information ratio {}, up capture {}, down capture {}, correlation {}",
+        name,
+        benchmark_name,
+        stats.alpha * 100.,
+        stats.beta,
+        stats.tracking_error * 100.,
+        format_ratio(stats.information_ratio),
+        format_ratio(stats.up_capture),
+        format_ratio(stats.down_capture),
+        format_ratio(stats.correlation)
+    );
+    for days in [30, 90, 365] {
+        let window = Duration::days(days);
+        let benchmark_returns: HashMap<_, _> = rolling_windows(benchmark, window, risk_free_rate)
+            .into_iter()
+            .map(|rolling| (rolling.timestamp, rolling.return_pct))
+            .collect();
+        let beats: Vec<bool> = rolling_windows(account, window, risk_free_rate)
+            .iter()
+            .filter_map(|rolling| Some(rolling.return_pct > *benchmark_returns.get(&rolling.timestamp)?))
+            .collect();
+        if !beats.is_empty() {
+            let share = beats.iter().filter(|beat| **beat).count() as f64 / beats.len() as f64;
+            info!(
+                "{} vs {}: ahead in {:.01}% of rolling {}-day windows",
+                name,
+                benchmark_name,
+                share * 100.,
+                days
+            );
+        }
+    }
+}
+
+/// one run in a comparison table
+#[derive(Debug, Clone)]
+pub struct ComparisonRow {
+    pub symbol: String,
+    pub period: String,
+    pub strategy: String,
+    pub equity: f64,
+    pub performance: Performance,
+    pub ratios: RiskRatios,
+}
+
+impl ComparisonRow {
+    pub fn new(symbol: &str, period: &str, strategy: &str, account: &Account, risk_free_rate: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            period: period.to_string(),
+            strategy: strategy.to_string(),
+            equity: account.equity(),
+            performance: Performance::from_account(account),
+            ratios: RiskRatios::from_account(account, risk_free_rate),
+        }
+    }
+
+    fn max_drawdown(&self) -> f64 {
+        self.performance.max_drawdown().map_or(0., |drawdown| drawdown.depth)
+    }
+
+    /// higher is better, undefined ratios rank last
+    pub fn score(&self, rank_by: RankBy) -> f64 {
+        let score = match rank_by {
+            RankBy::Return => Some(self.performance.total_return),
+            RankBy::Cagr => self.performance.cagr,
+            RankBy::Sharpe => self.ratios.sharpe,
+            RankBy::Sortino => self.ratios.sortino,
+            RankBy::Calmar => self.ratios.calmar,
+            RankBy::Drawdown => Some(-self.max_drawdown()),
+        };
+        score.filter(|score| !score.is_nan()).unwrap_or(f64::NEG_INFINITY)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RankBy {
+    Return,
+    Cagr,
+    Sharpe,
+    Sortino,
+    Calmar,
+    /// shallowest max drawdown first
+    Drawdown,
+}
+
+impl RankBy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RankBy::Return => "return",
+            RankBy::Cagr => "CAGR",
+            RankBy::Sharpe => "Sharpe",
+            RankBy::Sortino => "Sortino",
+            RankBy::Calmar => "Calmar",
+            RankBy::Drawdown => "drawdown",
+        }
+    }
+}
+
+/// the runs ranked best first
+pub fn comparison_table(rows: &mut [ComparisonRow], rank_by: RankBy) -> String {
+    rows.sort_by(|a, b| b.score(rank_by).total_cmp(&a.score(rank_by)));
+    let format_ratio = |ratio: Option<f64>| ratio.map_or("n/a".to_string(), |ratio| format!("{:.02}", ratio));
+    // grid search names carry their parameters and run long
+    let width = rows.iter().map(|row| row.strategy.len()).max().unwrap_or(0).max(12);
+    let mut table = format!(
+        "{:>4}  {:<10} {:<22} {:<width$} {:>12} {:>10} {:>8} {:>9} {:>7} {:>7} {:>7}\n",
+        "rank",
+        "symbol",
+        "period",
+        "strategy",
+        "equity",
+        "return",
+        "CAGR",
+        "max dd",
+        "Sharpe",
+        "Sortino",
+        "Calmar",
+        width = width
+    );
+    for (rank, row) in rows.iter().enumerate() {
+        table.push_str(&format!(
+            "{:>4}  {:<10} {:<22} {:<width$} {:>12.02} {:>9.02}% {:>7.02}% {:>8.02}% {:>7} {:>7} {:>7}\n",
+            rank + 1,
+            row.symbol,
+            row.period,
+            row.strategy,
+            row.equity,
+            row.performance.total_return * 100.,
+            row.performance.cagr.unwrap_or(f64::NAN) * 100.,
+            row.max_drawdown() * 100.,
+            format_ratio(row.ratios.sharpe),
+            format_ratio(row.ratios.sortino),
+            format_ratio(row.ratios.calmar),
+            width = width
+        ));
+    }
+    table
+}
+
+/// a walk-forward's windows in order, the parameters chosen in sample next to how they did out of sample,
+/// then the stitched out-of-sample run
+pub fn walk_forward_table(walk: &WalkForward, objective: RankBy) -> String {
+    let format_ratio = |ratio: Option<f64>| ratio.map_or("n/a".to_string(), |ratio| format!("{:.02}", ratio));
+    let width = walk
+        .windows
+        .iter()
+        .map(|window| window.chosen.row.strategy.len())
+        .max()
+        .unwrap_or(0)
+        .max(12);
+    let mut table = format!("{} {}, best {} in sample\n", walk.symbol, walk.period, objective.name());
+    table.push_str(&format!(
+        "{:<22} {:<22} {:<width$} {:>10} {:>10} {:>9} {:>7}\n",
+        "in sample",
+        "out of sample",
+        "chosen",
+        "IS score",
+        "OOS return",
+        "OOS dd",
+        "Sharpe",
+        width = width
+    ));
+    for window in walk.windows.iter() {
+        let row = &window.out_of_sample_row;
+        table.push_str(&format!(
+            "{:<22} {:<22} {:<width$} {:>10.04} {:>9.02}% {:>8.02}% {:>7}\n",
+            window.in_sample.to_string(),
+            window.out_of_sample.to_string(),
+            window.chosen.row.strategy,
+            window.chosen.row.score(objective),
+            row.performance.total_return * 100.,
+            row.max_drawdown() * 100.,
+            format_ratio(row.ratios.sharpe),
+            width = width
+        ));
+    }
+    match (&walk.performance, &walk.ratios, walk.compounded_capital.last()) {
+        (Some(performance), Some(ratios), Some((_, capital))) => table.push_str(&format!(
+            "out of sample: compounded capital {:.02}, return {:.02}%, CAGR {:.02}%, max dd {:.02}%, Sharpe {}, \
+             Sortino {}\n",
+            capital,
+            performance.total_return * 100.,
+            performance.cagr.unwrap_or(f64::NAN) * 100.,
+            performance.max_drawdown().map_or(0., |drawdown| drawdown.depth) * 100.,
+            format_ratio(ratios.sharpe),
+            format_ratio(ratios.sortino)
+        )),
+        _ => table.push_str("out of sample: no window had klines on both sides\n"),
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Drawdown;
+    use chrono::NaiveDate;
+
+    /// `ratios` are Sharpe, Sortino and Calmar
+    fn row(
+        strategy: &str,
+        total_return: f64,
+        cagr: Option<f64>,
+        depth: f64,
+        ratios: [Option<f64>; 3],
+    ) -> ComparisonRow {
+        let start = NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0);
+        let end = NaiveDate::from_ymd(2022, 1, 1).and_hms(0, 0, 0);
+        let [sharpe, sortino, calmar] = ratios;
+        ComparisonRow {
+            symbol: "ETHUSDT".to_string(),
+            period: "2021-01-01..2022-01-01".to_string(),
+            strategy: strategy.to_string(),
+            equity: 1000. * (1. + total_return),
+            performance: Performance {
+                start,
+                end,
+                total_return,
+                cagr,
+                drawdowns: vec![Drawdown {
+                    depth,
+                    start,
+                    trough: end,
+                    recovery: None,
+                }],
+            },
+            ratios: RiskRatios {
+                volatility: 0.5,
+                sharpe,
+                sortino,
+                calmar,
+                omega: None,
+            },
+        }
+    }
+
+    fn rows() -> Vec<ComparisonRow> {
+        vec![
+            row("C", -0.05, None, 0.4, [Some(0.2), Some(0.3), None]),
+            row("A", 0.3, Some(0.5), 0.25, [Some(f64::NAN), None, Some(2.)]),
+            row("B", 0.1, Some(0.15), 0.05, [Some(1.5), Some(2.), Some(3.)]),
+        ]
+    }
+
+    /// the rank and strategy columns of every line below the header
+    fn ranking(table: &str) -> Vec<(String, String)> {
+        table
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                (columns[0].to_string(), columns[3].to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_comparison_table_ranking() {
+        for (rank_by, expected) in [
+            (RankBy::Return, ["A", "B", "C"]),
+            (RankBy::Cagr, ["A", "B", "C"]),
+            (RankBy::Sharpe, ["B", "C", "A"]),
+            (RankBy::Sortino, ["B", "C", "A"]),
+            (RankBy::Calmar, ["B", "A", "C"]),
+            (RankBy::Drawdown, ["B", "A", "C"]),
+        ] {
+            let mut rows = rows();
+            let table = comparison_table(&mut rows, rank_by);
+            let strategies: Vec<&str> = rows.iter().map(|row| row.strategy.as_str()).collect();
+            assert_eq!(strategies, expected, "ranked by {}", rank_by.name());
+            let expected: Vec<(String, String)> = expected
+                .iter()
+                .enumerate()
+                .map(|(i, strategy)| ((i + 1).to_string(), strategy.to_string()))
+                .collect();
+            assert_eq!(ranking(&table), expected, "ranked by {}", rank_by.name());
+        }
+    }
+
+    #[test]
+    fn test_undefined_scores_rank_last() {
+        let rows = rows();
+        assert_eq!(rows[1].score(RankBy::Sharpe), f64::NEG_INFINITY);
+        assert_eq!(rows[1].score(RankBy::Sortino), f64::NEG_INFINITY);
+        assert_eq!(rows[0].score(RankBy::Cagr), f64::NEG_INFINITY);
+        assert_eq!(rows[2].score(RankBy::Drawdown), -0.05);
+    }
+}
@@ -0,0 +1,386 @@
+use crate::account::Account;
+#[cfg(feature = "charts")]
+use crate::charts::{load_font, symbol_charts, ChartFormat};
+use crate::comparison::{run_matrix, Dataset};
+use crate::config::{Experiment, Period, StrategyConfig};
+use crate::data::{get_cached_kline_data, BinanceKline, KlineCache, SymbolRules};
+use crate::export::{export_grid, export_runs, export_walk_forward, ExportFormat, RunExport};
+use crate::grid::{grid_search, GridConfig, GridResult};
+use crate::html::HtmlReport;
+use crate::report::{log_account, log_benchmark, log_summary, ComparisonRow, RankBy};
+use crate::strategy::{run_strategy, Strategy, StrategySettings};
+#[cfg(feature = "tui")]
+use crate::tui::SymbolRuns;
+use crate::walk_forward::{walk_forward, WalkForward};
+use chrono::NaiveDate;
+use log::{error, info};
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// where a command writes its runs besides the log and what it prints
+#[derive(Debug, Clone, Default)]
+pub struct Outputs {
+    /// directory every run's summary, trades and equity curve are exported into
+    pub export: Option<PathBuf>,
+    pub export_formats: Vec<ExportFormat>,
+    /// single-file html report
+    pub html: Option<PathBuf>,
+    /// directory the static charts are rendered into
+    #[cfg(feature = "charts")]
+    pub charts: Option<PathBuf>,
+    #[cfg(feature = "charts")]
+    pub chart_formats: Vec<ChartFormat>,
+    /// font for the chart labels, a common system font when not given
+    #[cfg(feature = "charts")]
+    pub font: Option<PathBuf>,
+}
+
+/// log every file written, or why writing them failed
+fn log_written<E: Display>(result: Result<Vec<PathBuf>, E>, what: &str, target: &Path) {
+    match result {
+        Ok(files) => {
+            for file in files {
+                info!("wrote [{}]", file.display());
+            }
+        }
+        Err(e) => error!("unable to write {} to [{}]: {}", what, target.display(), e),
+    }
+}
+
+/// the runs of a command gathered for the export and the html report, charts are rendered as they come in
+struct Collected<'a> {
+    outputs: &'a Outputs,
+    /// several periods of one symbol need telling apart in the report and the chart file names
+    label_periods: bool,
+    risk_free_rate: f64,
+    runs: Vec<RunExport>,
+    html: HtmlReport,
+}
+
+impl<'a> Collected<'a> {
+    fn new(outputs: &'a Outputs, experiment: &Experiment, label_periods: bool) -> Self {
+        let data = &experiment.data;
+        let title = format!(
+            "{} {} from {} to {}",
+            data.symbols.join(", "),
+            data.interval,
+            data.from,
+            data.to
+        );
+        Self {
+            outputs,
+            label_periods,
+            risk_free_rate: experiment.risk_free_rate,
+            runs: Vec::new(),
+            html: HtmlReport::new(&title, experiment.risk_free_rate),
+        }
+    }
+
+    /// `accounts` are the runs of `strategies`, in the same order
+    fn add(
+        &mut self,
+        symbol: &str,
+        period: Period,
+        klines: &[BinanceKline],
+        strategies: &[StrategyConfig],
+        accounts: &[(String, Account)],
+    ) {
+        for (name, account) in accounts.iter() {
+            let run = RunExport::new(symbol, period, name, account, klines, self.risk_free_rate);
+            self.runs.push(run);
+        }
+        let (title, file) = if self.label_periods {
+            (
+                format!("{} {}", symbol, period),
+                format!("{}-{}-{}", symbol, period.from, period.to),
+            )
+        } else {
+            (symbol.to_string(), symbol.to_string())
+        };
+        self.html.add_symbol(&title, klines, accounts);
+        self.charts(&file, klines, strategies, accounts);
+    }
+
+    #[cfg(feature = "charts")]
+    fn charts(&self, file: &str, klines: &[BinanceKline], strategies: &[StrategyConfig], accounts: &[(String, Account)]) {
+        let dir = match &self.outputs.charts {
+            Some(dir) => dir,
+            None => return,
+        };
+        if let Err(e) = load_font(self.outputs.font.as_deref()) {
+            error!("unable to render charts: {}", e);
+            return;
+        }
+        let runs: Vec<(&StrategyConfig, &Account)> =
+            strategies.iter().zip(accounts.iter().map(|(_, account)| account)).collect();
+        let written = symbol_charts(dir, &self.outputs.chart_formats, file, klines, &runs);
+        log_written(written, "the charts", dir);
+    }
+
+    #[cfg(not(feature = "charts"))]
+    fn charts(&self, _file: &str, _klines: &[BinanceKline], _strategies: &[StrategyConfig], _accounts: &[(String, Account)]) {
+    }
+
+    fn finish(self) {
+        if let Some(dir) = &self.outputs.export {
+            log_written(export_runs(&self.runs, dir, &self.outputs.export_formats), "the runs", dir);
+        }
+        if let Some(path) = &self.outputs.html {
+            match fs::write(path, self.html.finish()) {
+                Ok(()) => info!("wrote the html report to [{}]", path.display()),
+                Err(e) => error!("unable to write [{}]: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+/// size the pool the runs are spread over, rayon uses one thread per core unless told otherwise
+pub fn set_jobs(jobs: Option<usize>) {
+    if let Some(jobs) = jobs {
+        if let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(jobs).build_global() {
+            error!("unable to start {} threads: {}", jobs, e);
+        }
+    }
+}
+
+/// runs experiments on cached klines, with each symbol's trading rules from a Binance exchangeInfo file
+pub struct Runner {
+    cache: KlineCache,
+    exchange_info: PathBuf,
+}
+
+impl Runner {
+    pub fn new(cache: KlineCache, exchange_info: &Path) -> Self {
+        Self {
+            cache,
+            exchange_info: exchange_info.to_path_buf(),
+        }
+    }
+
+    pub fn cache(&self) -> &KlineCache {
+        &self.cache
+    }
+
+    pub async fn download(&self, symbol: &str, interval: &str, from: NaiveDate, to: NaiveDate) -> Vec<BinanceKline> {
+        info!(
+            "download data from binance for [{}/{}] from [{}] to [{}]",
+            symbol, interval, from, to
+        );
+        let klines = get_cached_kline_data(&self.cache, symbol, interval, from, to).await;
+        info!("downloaded [{}] klines", klines.len());
+        klines
+    }
+
+    fn symbol_rules(&self, symbol: &str) -> Option<SymbolRules> {
+        match SymbolRules::from_exchange_info(&self.exchange_info, symbol) {
+            Ok(rules) => {
+                info!("loaded trading rules for [{}]: {:?}", symbol, rules);
+                Some(rules)
+            }
+            Err(e) => {
+                info!("no trading rules for [{}] from [{}]: {}", symbol, self.exchange_info.display(), e);
+                None
+            }
+        }
+    }
+
+    fn settings(&self, experiment: &Experiment, symbol: &str) -> StrategySettings {
+        let mut settings = experiment.settings();
+        settings.symbol_rules = self.symbol_rules(symbol);
+        settings
+    }
+
+    /// every symbol's klines cut into the experiment's periods
+    pub async fn datasets(&self, experiment: &Experiment) -> Vec<Dataset> {
+        let data = &experiment.data;
+        let periods = data.periods();
+        let mut datasets = Vec::new();
+        for symbol in data.symbols.iter() {
+            let klines = self.download(symbol, &data.interval, data.from, data.to).await;
+            let settings = self.settings(experiment, symbol);
+            for period in periods.iter() {
+                datasets.push(Dataset::new(symbol, *period, &klines, settings.clone()));
+            }
+        }
+        datasets
+    }
+
+    /// each strategy on one symbol over the whole experiment, in the order given
+    pub async fn backtest_symbol(
+        &self,
+        experiment: &Experiment,
+        strategies: &[StrategyConfig],
+        symbol: &str,
+    ) -> (Vec<BinanceKline>, Vec<(String, Account)>) {
+        let data = &experiment.data;
+        let klines = self.download(symbol, &data.interval, data.from, data.to).await;
+        if klines.is_empty() {
+            info!("no klines for [{}], skipping", symbol);
+            return (klines, Vec::new());
+        }
+        let settings = self.settings(experiment, symbol);
+        let accounts = strategies
+            .iter()
+            .map(|strategy| {
+                let account = run_strategy(strategy.strategy(), &klines, &strategy.settings(&settings));
+                (strategy.name(), account)
+            })
+            .collect();
+        (klines, accounts)
+    }
+
+    /// every strategy on every symbol, logging a summary of each run
+    pub async fn backtest(&self, experiment: &Experiment, outputs: &Outputs) {
+        let rf = experiment.risk_free_rate;
+        let period = Period {
+            from: experiment.data.from,
+            to: experiment.data.to,
+        };
+        let mut collected = Collected::new(outputs, experiment, false);
+        for symbol in experiment.data.symbols.iter() {
+            let (klines, accounts) = self.backtest_symbol(experiment, &experiment.strategies, symbol).await;
+            for (name, account) in accounts.iter() {
+                log_summary(&format!("{} {}", symbol, name), account, rf);
+            }
+            collected.add(symbol, period, &klines, &experiment.strategies, &accounts);
+        }
+        collected.finish();
+    }
+
+    /// every strategy on every symbol and period, spread over the rayon pool, one row per run to rank
+    pub async fn compare(&self, experiment: &Experiment, outputs: &Outputs) -> Vec<ComparisonRow> {
+        let rf = experiment.risk_free_rate;
+        let periods = experiment.data.periods();
+        let datasets = self.datasets(experiment).await;
+        info!(
+            "running {} strategies on {} symbol(s) over {} period(s)",
+            experiment.strategies.len(),
+            experiment.data.symbols.len(),
+            periods.len()
+        );
+        let matrix = run_matrix(&datasets, &experiment.strategies);
+        let mut rows = Vec::new();
+        let mut collected = Collected::new(outputs, experiment, periods.len() > 1);
+        for (dataset, accounts) in datasets.iter().zip(matrix.iter()) {
+            if accounts.is_empty() {
+                info!("no klines for [{}] over {}, skipping", dataset.symbol, dataset.period);
+                continue;
+            }
+            let period = dataset.period.to_string();
+            for (name, account) in accounts.iter() {
+                rows.push(ComparisonRow::new(&dataset.symbol, &period, name, account, rf));
+            }
+            collected.add(&dataset.symbol, dataset.period, &dataset.klines, &experiment.strategies, accounts);
+        }
+        collected.finish();
+        rows
+    }
+
+    /// HODL on `symbol` over the whole experiment, named after the symbol so it reads as a benchmark for others
+    async fn benchmark(&self, experiment: &Experiment, symbol: &str) -> Option<(String, Account)> {
+        let hodl = [StrategyConfig::Hodl { name: None }];
+        let (_, mut accounts) = self.backtest_symbol(experiment, &hodl, symbol).await;
+        accounts.pop().map(|(name, account)| (format!("{} {}", symbol, name), account))
+    }
+
+    /// every metric of each run, and how each strategy did against the experiment's benchmark, or else against
+    /// HODL on the same symbol
+    pub async fn report(&self, experiment: &Experiment, outputs: &Outputs) {
+        let rf = experiment.risk_free_rate;
+        let period = Period {
+            from: experiment.data.from,
+            to: experiment.data.to,
+        };
+        let shared = match &experiment.benchmark {
+            Some(symbol) => {
+                let benchmark = self.benchmark(experiment, symbol).await;
+                if benchmark.is_none() {
+                    error!("no klines for the benchmark [{}], measuring against each symbol's HODL", symbol);
+                }
+                benchmark
+            }
+            None => None,
+        };
+        // HODL is always run last as the benchmark, even when it was not asked for
+        let mut strategies = experiment.strategies.clone();
+        strategies.push(StrategyConfig::Hodl { name: None });
+        let mut collected = Collected::new(outputs, experiment, false);
+        for symbol in experiment.data.symbols.iter() {
+            let (klines, mut accounts) = self.backtest_symbol(experiment, &strategies, symbol).await;
+            let own = match accounts.pop() {
+                Some(own) => own,
+                None => continue,
+            };
+            let external = shared.as_ref().filter(|_| experiment.benchmark.as_ref() != Some(symbol));
+            let (benchmark_name, benchmark) = external.unwrap_or(&own);
+            for (name, account) in accounts.iter() {
+                log_account(&format!("{} {}", symbol, name), account, &klines, rf);
+            }
+            // HODL says nothing against itself, but does against another symbol's HODL
+            let runs = accounts
+                .iter()
+                .zip(experiment.strategies.iter())
+                .filter(|(_, strategy)| external.is_some() || strategy.strategy() != Strategy::Hodl);
+            for ((name, account), _) in runs {
+                log_benchmark(&format!("{} {}", symbol, name), account, benchmark_name, benchmark, rf);
+            }
+            collected.add(symbol, period, &klines, &experiment.strategies, &accounts);
+        }
+        collected.finish();
+    }
+
+    /// every strategy on every symbol, ready to be explored
+    #[cfg(feature = "tui")]
+    pub async fn explore(&self, experiment: &Experiment) -> Vec<SymbolRuns> {
+        let mut symbols = Vec::new();
+        for symbol in experiment.data.symbols.iter() {
+            let (klines, accounts) = self.backtest_symbol(experiment, &experiment.strategies, symbol).await;
+            let runs = experiment.strategies.iter().cloned().zip(accounts.into_iter().map(|(_, account)| account));
+            symbols.push(SymbolRuns::new(symbol, klines, runs.collect()));
+        }
+        symbols
+    }
+
+    /// every combination of the experiment's grid on every symbol and period, each dataset's results in
+    /// strategy order, empty for a dataset without klines
+    pub async fn grid(&self, experiment: &Experiment, outputs: &Outputs) -> Vec<Vec<GridResult>> {
+        let datasets = self.datasets(experiment).await;
+        let strategies: Vec<StrategyConfig> = experiment.grid.iter().flat_map(GridConfig::strategies).collect();
+        info!(
+            "searching {} parameter combinations on {} dataset(s)",
+            strategies.len(),
+            datasets.len()
+        );
+        let results = grid_search(&datasets, &strategies, experiment.risk_free_rate);
+        for (dataset, results) in datasets.iter().zip(results.iter()) {
+            if results.is_empty() {
+                info!("no klines for [{}] over {}, skipping", dataset.symbol, dataset.period);
+            }
+        }
+        if let Some(dir) = &outputs.export {
+            let flat: Vec<GridResult> = results.iter().flatten().cloned().collect();
+            log_written(export_grid(&flat, dir, &outputs.export_formats), "the grid", dir);
+        }
+        results
+    }
+
+    /// the experiment's grid optimised by `objective` on rolling in-sample windows and traded out of sample
+    pub async fn walk_forward(&self, experiment: &Experiment, objective: RankBy, outputs: &Outputs) -> Vec<WalkForward> {
+        let config = experiment.walk_forward.unwrap_or_default();
+        let datasets = self.datasets(experiment).await;
+        let strategies: Vec<StrategyConfig> = experiment.grid.iter().flat_map(GridConfig::strategies).collect();
+        info!(
+            "walking {} parameter combinations forward on {} dataset(s), {} days in sample and {} out",
+            strategies.len(),
+            datasets.len(),
+            config.in_sample_days,
+            config.out_of_sample_days
+        );
+        let walks = walk_forward(&datasets, &strategies, config, objective, experiment.risk_free_rate);
+        if let Some(dir) = &outputs.export {
+            log_written(export_walk_forward(&walks, dir, &outputs.export_formats), "the walk-forward", dir);
+        }
+        walks
+    }
+}
@@ -0,0 +1,202 @@
+use crate::account::{Account, CashFlow, Frequency, Position};
+use crate::backtest::{run_backtest, ExecutionTiming};
+use crate::data::{BinanceKline, SymbolRules};
+use crate::traders::{
+    DCATrader, HODLTrader, MACDTrader, MacdPeriods, RiskExit, RiskOverlay, Slippage, StakeSize, TradingFee,
+};
+use clap::ValueEnum;
+
+use log::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum)]
+pub enum Strategy {
+    Macd,
+    MacdTrailingStop,
+    Hodl,
+    Dca,
+}
+
+impl Strategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Macd => "MACD",
+            Strategy::MacdTrailingStop => "MACD+TS",
+            Strategy::Hodl => "HODL",
+            Strategy::Dca => "DCA",
+        }
+    }
+}
+
+/// everything about a backtest run other than the strategy and the data
+#[derive(Debug, Clone)]
+pub struct StrategySettings {
+    /// starting cash, DCA starts with none and is funded by its deposits alone
+    pub capital: f64,
+    pub trading_fee: TradingFee,
+    pub slippage: Slippage,
+    pub stake_size: StakeSize,
+    pub timing: ExecutionTiming,
+    pub symbol_rules: Option<SymbolRules>,
+    /// deposited and bought at the start of every `dca_frequency` period for DCA
+    pub dca_amount: f64,
+    pub dca_frequency: Frequency,
+    pub trailing_stop: f64,
+    pub macd: MacdPeriods,
+}
+
+impl Default for StrategySettings {
+    fn default() -> Self {
+        Self {
+            capital: 1000.,
+            trading_fee: TradingFee::PercentageFee(0.005),
+            slippage: Slippage::Zero,
+            stake_size: StakeSize::FixPercentage(1.),
+            timing: ExecutionTiming::NextBarOpen(1),
+            symbol_rules: None,
+            dca_amount: 100.,
+            dca_frequency: Frequency::Monthly,
+            trailing_stop: 0.08,
+            macd: MacdPeriods::default(),
+        }
+    }
+}
+
+fn initialise_account(klines: &[BinanceKline], capital: f64, settings: &StrategySettings) -> Account {
+    let first_kline = klines.first().expect("no klines fetched");
+    let start_position = Position {
+        quantity: 0.0,
+        cost: 0.0,
+    };
+    let mut account = Account::new(capital, start_position, first_kline.start_time);
+    account.dust = settings.symbol_rules.map_or(0., |rules| rules.dust());
+    account
+}
+
+pub fn run_strategy(strategy: Strategy, klines: &[BinanceKline], settings: &StrategySettings) -> Account {
+    info!("backtesting {} on {} klines", strategy.name(), klines.len());
+    // starting cash DCA never buys with would sit idle and drag its return down
+    let capital = match strategy {
+        Strategy::Dca => 0.,
+        _ => settings.capital,
+    };
+    let mut account = initialise_account(klines, capital, settings);
+    let mut klines_iter = klines.iter().copied();
+    let trading_fee = settings.trading_fee.clone();
+    match strategy {
+        Strategy::Macd => {
+            let mut trader = MACDTrader::new(
+                &mut klines_iter,
+                settings.macd,
+                trading_fee,
+                settings.stake_size,
+                settings.slippage,
+                settings.symbol_rules,
+            );
+            run_backtest(&mut trader, &mut account, settings.timing);
+        }
+        Strategy::MacdTrailingStop => {
+            let trader = MACDTrader::new(
+                &mut klines_iter,
+                settings.macd,
+                trading_fee,
+                settings.stake_size,
+                settings.slippage,
+                settings.symbol_rules,
+            );
+            let mut trader = RiskOverlay::new(trader, vec![RiskExit::TrailingStop(settings.trailing_stop)]);
+            run_backtest(&mut trader, &mut account, settings.timing);
+        }
+        Strategy::Hodl => {
+            let mut trader = HODLTrader::new(&mut klines_iter, trading_fee, settings.slippage, settings.symbol_rules);
+            run_backtest(&mut trader, &mut account, settings.timing);
+        }
+        Strategy::Dca => {
+            let start_date = klines.first().expect("no klines fetched").start_time.date();
+            let end_date = klines.last().expect("no klines fetched").end_time.date();
+            let frequency = settings.dca_frequency;
+            account.schedule_cash_flows(CashFlow::every(frequency, start_date, end_date, settings.dca_amount));
+            let mut trader = DCATrader::new(
+                &mut klines_iter,
+                settings.dca_amount,
+                frequency,
+                trading_fee,
+                settings.slippage,
+                settings.symbol_rules,
+            );
+            run_backtest(&mut trader, &mut account, settings.timing);
+        }
+    }
+    account
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines;
+    use chrono::NaiveDate;
+
+    fn klines() -> Vec<BinanceKline> {
+        test_klines(NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0), 24 * 90, 0.)
+    }
+
+    #[test]
+    fn test_every_strategy_reconciles() {
+        let klines = klines();
+        let settings = StrategySettings::default();
+        for strategy in Strategy::value_variants() {
+            let account = run_strategy(*strategy, &klines, &settings);
+            assert_eq!(account.audit(), Ok(()), "{} does not reconcile", strategy.name());
+            assert!(!account.trade_history.is_empty(), "{} never traded", strategy.name());
+        }
+    }
+
+    #[test]
+    fn test_dca_deposits_monthly() {
+        let settings = StrategySettings {
+            dca_amount: 50.,
+            ..StrategySettings::default()
+        };
+        let account = run_strategy(Strategy::Dca, &klines(), &settings);
+        assert_eq!(account.net_contribution(), 3. * 50.);
+    }
+
+    #[test]
+    fn test_dca_invests_every_deposit() {
+        let account = run_strategy(Strategy::Dca, &klines(), &StrategySettings::default());
+        assert_eq!(account.net_contribution(), 3. * 100.);
+        assert_eq!(account.trade_history.len(), 3);
+        assert!(account.available_fund.abs() < 1e-9, "{} left in cash", account.available_fund);
+    }
+
+    #[test]
+    fn test_dca_weekly() {
+        let settings = StrategySettings {
+            dca_amount: 20.,
+            dca_frequency: Frequency::Weekly,
+            ..StrategySettings::default()
+        };
+        let account = run_strategy(Strategy::Dca, &klines(), &settings);
+        // every Monday from 2021-11-01, itself a Monday, until 2022-01-24
+        assert_eq!(account.net_contribution(), 13. * 20.);
+        assert_eq!(account.trade_history.len(), 13);
+        assert!(account.available_fund.abs() < 1e-9, "{} left in cash", account.available_fund);
+        assert!(account.trade_history.iter().all(|trade| (trade.quantity() * trade.price() - 20.).abs() < 1.));
+    }
+
+    #[test]
+    fn test_macd_periods() {
+        let klines = klines();
+        let default = run_strategy(Strategy::Macd, &klines, &StrategySettings::default());
+        let settings = StrategySettings {
+            macd: MacdPeriods {
+                fast: 5,
+                slow: 40,
+                signal: 9,
+            },
+            ..StrategySettings::default()
+        };
+        let slower = run_strategy(Strategy::Macd, &klines, &settings);
+        assert_ne!(default.equity(), slower.equity());
+        assert_ne!(default.trade_history[0].timestamp(), slower.trade_history[0].timestamp());
+    }
+}
@@ -1,5 +1,6 @@
-use crate::data::BinanceKline;
-use crate::traders::{GenericTrader, StakeSize, TradingFee};
+use crate::account::Frequency;
+use crate::data::{BinanceKline, SymbolRules};
+use crate::traders::{GenericTrader, Slippage, StakeSize, TradingFee};
 use crate::indicators::BinanceIndicatorInstance;
 use crate::indicators::DCA;
 use yata::core::{Action};
@@ -10,6 +11,8 @@ use log::debug;
 pub struct DCATrader<'a> {
     trading_fee: TradingFee,
     stake_size: StakeSize,
+    slippage: Slippage,
+    symbol_rules: Option<SymbolRules>,
     kline_feed: &'a mut dyn Iterator<Item = BinanceKline>,
     indicator: Box<dyn BinanceIndicatorInstance>,
 }
@@ -17,16 +20,22 @@ pub struct DCATrader<'a> {
 impl<'a> DCATrader<'a> {
     pub fn new(
         kline_feed: &'a mut dyn Iterator<Item = BinanceKline>,
+        amount: f64,
+        frequency: Frequency,
         trading_fee: TradingFee,
+        slippage: Slippage,
+        symbol_rules: Option<SymbolRules>,
     ) -> Self {
         debug!("creating a DCA Trader");
-        let dca = DCA::default();
+        let dca = DCA { frequency };
         let dca = dca.init(&kline_feed.next().unwrap()).expect("Unable to initialise DCA");
         Self {
             kline_feed,
             indicator: Box::new(dca),
             trading_fee,
-            stake_size: StakeSize::FixAmount(100.0),
+            slippage,
+            symbol_rules,
+            stake_size: StakeSize::FixAmount(amount),
         }
     }
 }
@@ -37,8 +46,16 @@ impl<'a> GenericTrader<'a> for DCATrader<'a> {
         self.stake_size
     }
 
-    fn trading_fee(&self) -> TradingFee {
-        self.trading_fee
+    fn trading_fee(&self) -> &TradingFee {
+        &self.trading_fee
+    }
+
+    fn slippage(&self) -> Slippage {
+        self.slippage
+    }
+
+    fn symbol_rules(&self) -> Option<SymbolRules> {
+        self.symbol_rules
     }
 
     fn kline(&mut self) -> &mut dyn Iterator<Item = BinanceKline> {
@@ -51,6 +68,6 @@ impl<'a> GenericTrader<'a> for DCATrader<'a> {
 
     fn determine_trade(signals: &[Action]) -> Action {
         debug!("determine trades with dca signal");
-        *signals.get(0).unwrap()
+        *signals.first().unwrap()
     }
 }
\ No newline at end of file
@@ -1,19 +1,13 @@
-use crate::data::BinanceKline;
+use crate::data::{BinanceKline, RejectReason, SymbolRules};
 use crate::indicators::BinanceIndicatorInstance;
-use crate::account::Account;
-use chrono::NaiveDateTime;
+use crate::account::{Account, BuySellIndicator, FeeAsset};
+use crate::orders::{Fill, OrderBook, OrderSide};
+use crate::traders::{Liquidity, Slippage, TradingFee};
+use chrono::{Duration, NaiveDateTime};
 use yata::core::Action;
 use log::debug;
 
-#[allow(dead_code)]
-#[derive(Clone, Copy)]
-pub enum TradingFee {
-    FixFee(f64),
-    PercentageFee(f64),
-}
-
-#[allow(dead_code)]
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub enum StakeSize {
     FixAmount(f64),
     FixPercentage(f64),
@@ -22,57 +16,160 @@ pub enum StakeSize {
 pub trait GenericTrader<'a> {
     fn determine_trade(signals: &[Action]) -> Action;
     fn stake_size(&self) -> StakeSize;
-    fn trading_fee(&self) -> TradingFee;
+    fn trading_fee(&self) -> &TradingFee;
+    fn slippage(&self) -> Slippage;
+    fn symbol_rules(&self) -> Option<SymbolRules>;
     fn kline(&mut self) -> &mut dyn Iterator<Item = BinanceKline>;
     fn indicator(&mut self) -> &mut dyn BinanceIndicatorInstance;
 
-    fn execute_buy(&self, timestamp: NaiveDateTime, price: f64, account: &mut Account) {
+    fn volume_30d(&self, timestamp: NaiveDateTime, account: &Account) -> f64 {
+        account.rolling_volume(timestamp, Duration::days(30))
+    }
+
+    /// the notional `amount` of cash can buy, leaving room for the fee when it is paid in cash
+    fn buy_notional(&self, timestamp: NaiveDateTime, amount: f64, liquidity: Liquidity, account: &Account) -> f64 {
+        let volume = self.volume_30d(timestamp, account);
+        let fee = self.trading_fee().fee(amount, liquidity, volume);
+        match account.fee_asset_for(fee) {
+            FeeAsset::Quote => self.trading_fee().notional_for(amount, liquidity, volume),
+            _ => amount,
+        }
+    }
+
+    fn settle_buy(
+        &self,
+        timestamp: NaiveDateTime,
+        reference_price: f64,
+        fill_price: f64,
+        quantity: f64,
+        liquidity: Liquidity,
+        account: &mut Account,
+    ) {
+        if quantity <= 0.0 {
+            return;
+        }
+        let (quantity, fill_price) = match self.check_symbol_rules(timestamp, BuySellIndicator::Buy, quantity, fill_price, account) {
+            Some(order) => order,
+            None => return,
+        };
+        let volume = self.volume_30d(timestamp, account);
+        let fee = self.trading_fee().fee(fill_price * quantity, liquidity, volume);
+        debug!("{}, B {:.08} @ ${:.08}, available_fund: {:.02}", timestamp, quantity, fill_price, account.available_fund);
+        account.open(timestamp, quantity, fill_price, fee, (fill_price - reference_price) * quantity);
+    }
+
+    fn settle_sell(
+        &self,
+        timestamp: NaiveDateTime,
+        reference_price: f64,
+        fill_price: f64,
+        quantity: f64,
+        liquidity: Liquidity,
+        account: &mut Account,
+    ) {
+        if quantity <= 0.0 {
+            return;
+        }
+        let (quantity, fill_price) = match self.check_symbol_rules(timestamp, BuySellIndicator::Sell, quantity, fill_price, account) {
+            Some(order) => order,
+            None => return,
+        };
+        let volume = self.volume_30d(timestamp, account);
+        let fee = self.trading_fee().fee(fill_price * quantity, liquidity, volume);
+        debug!("{}, S {:.08} @ $ {:0.8}", timestamp, quantity, fill_price);
+        account.close(timestamp, quantity, fill_price, fee, (reference_price - fill_price) * quantity);
+    }
+
+    fn execute_buy(&self, timestamp: NaiveDateTime, price: f64, kline: &BinanceKline, account: &mut Account) {
         let fund = account.available_fund;
         let stake = match self.stake_size() {
-            StakeSize::FixAmount(amount) => if amount <= fund { amount } else { 0. },
+            // a fixed stake that only misses the fund by float rounding still buys with what is there
+            StakeSize::FixAmount(amount) => if fund >= amount * (1. - 1e-9) { amount.min(fund) } else { 0. },
             StakeSize::FixPercentage(pct) => fund * pct,
         };
-        let fee = match self.trading_fee() {
-            TradingFee::FixFee(fee) => fee,
-            TradingFee::PercentageFee(pct) => stake * pct / (1.0 - pct),
+        let notional = self.buy_notional(timestamp, stake, Liquidity::Taker, account);
+        let fill_price = self.slippage().buy_price(price, notional / price, kline);
+        let quantity = notional / fill_price;
+        self.settle_buy(timestamp, price, fill_price, quantity, Liquidity::Taker, account);
+    }
+
+    fn execute_sell(&self, timestamp: NaiveDateTime, price: f64, kline: &BinanceKline, account: &mut Account) {
+        let quantity = account.position.quantity;
+        let fill_price = self.slippage().sell_price(price, quantity, kline);
+        self.settle_sell(timestamp, price, fill_price, quantity, Liquidity::Taker, account);
+    }
+
+    fn execute(&self, action: Action, timestamp: NaiveDateTime, price: f64, kline: &BinanceKline, account: &mut Account) {
+        match action {
+            Action::Buy(_) => self.execute_buy(timestamp, price, kline, account),
+            Action::Sell(_) => self.execute_sell(timestamp, price, kline, account),
+            _ => debug!("nothing to do"),
         };
-        let quantity = (stake + fee) / price;
-        
-        if quantity > 0.0 {
-            debug!("{}, B {:.08} @ ${:.08}, available_fund: {:.02}", timestamp, quantity, price, fund - stake);
-            account.open(timestamp, quantity, price, fee);
-        }
     }
 
-    fn execute_sell(&self, timestamp: NaiveDateTime, price: f64, account: &mut Account) {
-        let current_position = account.position.quantity;
-        let fee = match self.trading_fee() {
-            TradingFee::FixFee(fee) => fee,
-            TradingFee::PercentageFee(pct) => price * current_position * pct,
+    fn check_symbol_rules(
+        &self,
+        timestamp: NaiveDateTime,
+        side: BuySellIndicator,
+        quantity: f64,
+        price: f64,
+        account: &mut Account,
+    ) -> Option<(f64, f64)> {
+        let rules = match self.symbol_rules() {
+            Some(rules) => rules,
+            None => return Some((quantity, price)),
         };
-        if current_position > 0.0 {
-            debug!("{}, S {:.08} @ $ {:0.8}", timestamp, current_position, price);
-            account.close(timestamp, current_position, price, fee)
+        match rules.apply(quantity, price) {
+            Ok(order) => Some(order),
+            Err(reason) => {
+                debug!("{}, {:?} {:.08} @ ${:.08} rejected: {:?}", timestamp, side, quantity, price, reason);
+                account.reject(timestamp, side, quantity, price, reason);
+                None
+            }
         }
     }
 
-    fn next_trade_session(&mut self, account: &mut Account) -> Option<BinanceKline> {
-        let kline = self.kline().next();
-        match kline {
-            None => None,
-            Some(kline) => {
-                let timestamp = kline.end_time;
-                let price = kline.close;
-
-                let indicator = self.indicator().next_binance_kline(&kline);
-                let signals = indicator.signals();
-                match Self::determine_trade(signals) {
-                    Action::Buy(_) => self.execute_buy(timestamp, price, account),
-                    Action::Sell(_) => self.execute_sell(timestamp, price, account),
-                    _ => debug!("nothing to do"),
+    fn execute_order(&self, fill: &Fill, timestamp: NaiveDateTime, kline: &BinanceKline, account: &mut Account) {
+        let order = fill.order;
+        let liquidity = if order.is_market_fill() {
+            Liquidity::Taker
+        } else {
+            Liquidity::Maker
+        };
+        debug!("{}, filling order #{} {:?}", timestamp, order.id, order.order_type);
+        match order.side {
+            OrderSide::Buy => {
+                let fill_price = match liquidity {
+                    Liquidity::Taker => self.slippage().buy_price(fill.price, order.quantity, kline),
+                    Liquidity::Maker => fill.price,
+                };
+                let affordable = self.buy_notional(timestamp, account.available_fund, liquidity, account) / fill_price;
+                let quantity = order.quantity.min(affordable);
+                if quantity < order.quantity {
+                    let shortfall = order.quantity - quantity.max(0.);
+                    debug!("{}, B {:.08} @ ${:.08} unfilled: {:?}", timestamp, shortfall, fill_price, RejectReason::InsufficientFunds);
+                    account.reject(timestamp, BuySellIndicator::Buy, shortfall, fill_price, RejectReason::InsufficientFunds);
+                }
+                self.settle_buy(timestamp, fill.price, fill_price, quantity, liquidity, account);
+            }
+            OrderSide::Sell => {
+                let quantity = order.quantity.min(account.position.quantity);
+                let fill_price = match liquidity {
+                    Liquidity::Taker => self.slippage().sell_price(fill.price, quantity, kline),
+                    Liquidity::Maker => fill.price,
                 };
-                Some(kline)
+                self.settle_sell(timestamp, fill.price, fill_price, quantity, liquidity, account);
             }
         }
     }
+
+    /// submit, amend or cancel resting orders after seeing the candle; new orders can fill from the next candle
+    fn manage_orders(&mut self, _kline: &BinanceKline, _account: &Account, _orders: &mut OrderBook) {}
+
+    fn next_signal(&mut self, _account: &Account) -> Option<(BinanceKline, Action)> {
+        let kline = self.kline().next()?;
+        let indicator = self.indicator().next_binance_kline(&kline);
+        let action = Self::determine_trade(indicator.signals());
+        Some((kline, action))
+    }
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-use crate::data::BinanceKline;
-use crate::traders::{GenericTrader, StakeSize, TradingFee};
+use crate::data::{BinanceKline, SymbolRules};
+use crate::traders::{GenericTrader, Slippage, StakeSize, TradingFee};
 use crate::indicators::BinanceIndicatorInstance;
 use crate::indicators::HODL;
 use yata::core::Action;
@@ -11,6 +11,8 @@ use log::debug;
 pub struct HODLTrader<'a> {
     trading_fee: TradingFee,
     stake_size: StakeSize,
+    slippage: Slippage,
+    symbol_rules: Option<SymbolRules>,
     kline_feed: &'a mut dyn Iterator<Item = BinanceKline>,
     indicator: Box<dyn BinanceIndicatorInstance>,
 }
@@ -19,6 +21,8 @@ impl<'a> HODLTrader<'a> {
     pub fn new(
         kline_feed: &'a mut dyn Iterator<Item = BinanceKline>,
         trading_fee: TradingFee,
+        slippage: Slippage,
+        symbol_rules: Option<SymbolRules>,
     ) -> Self {
         debug!("creating a HODL Trader");
         let hodl = HODL::default();
@@ -28,6 +32,8 @@ impl<'a> HODLTrader<'a> {
             kline_feed,
             indicator: hodl,
             trading_fee,
+            slippage,
+            symbol_rules,
             stake_size: StakeSize::FixPercentage(1.),
         }
     }
@@ -38,8 +44,16 @@ impl<'a> GenericTrader<'a> for HODLTrader<'a> {
         self.stake_size
     }
 
-    fn trading_fee(&self) -> TradingFee {
-        self.trading_fee
+    fn trading_fee(&self) -> &TradingFee {
+        &self.trading_fee
+    }
+
+    fn slippage(&self) -> Slippage {
+        self.slippage
+    }
+
+    fn symbol_rules(&self) -> Option<SymbolRules> {
+        self.symbol_rules
     }
 
     fn kline(&mut self) -> &mut dyn Iterator<Item = BinanceKline> {
@@ -52,6 +66,6 @@ impl<'a> GenericTrader<'a> for HODLTrader<'a> {
 
     fn determine_trade(signals: &[Action]) -> Action {
         debug!("determine trades with hodl signal");
-        *signals.get(0).unwrap()
+        *signals.first().unwrap()
     }
 }
\ No newline at end of file
@@ -1,6 +1,9 @@
-use crate::data::BinanceKline;
-use crate::traders::{GenericTrader, StakeSize, TradingFee};
+use crate::data::{BinanceKline, SymbolRules};
+use crate::traders::{GenericTrader, Slippage, StakeSize, TradingFee};
 use crate::indicators::BinanceIndicatorInstance;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
 use yata::core::{Action, IndicatorResult};
 use yata::indicators::MACD;
 use yata::prelude::dd::IndicatorInstanceDyn;
@@ -15,9 +18,70 @@ impl BinanceIndicatorInstance for IndicatorInstanceWrapper {
     }
 }
 
+/// the fast and slow moving averages and the signal line's, in bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MacdPeriods {
+    pub fast: u8,
+    pub slow: u8,
+    /// only moves the signal line, the trader acts on MACD crossing zero
+    pub signal: u8,
+}
+
+impl Default for MacdPeriods {
+    fn default() -> Self {
+        Self {
+            fast: 12,
+            slow: 26,
+            signal: 9,
+        }
+    }
+}
+
+impl MacdPeriods {
+    pub fn macd(&self) -> MACD {
+        MACD {
+            period1: self.fast,
+            period2: self.slow,
+            period3: self.signal,
+            ..MACD::default()
+        }
+    }
+
+    /// what yata accepts, the fast average has to be quicker than the slow one
+    pub fn is_valid(&self) -> bool {
+        self.macd().validate()
+    }
+}
+
+impl fmt::Display for MacdPeriods {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.fast, self.slow, self.signal)
+    }
+}
+
+impl FromStr for MacdPeriods {
+    type Err = String;
+
+    /// `fast/slow/signal`, e.g. `12/26/9`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let periods: Vec<u8> = s
+            .split('/')
+            .map(|period| period.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("expected periods like 12/26/9, got [{}]", s))?;
+        match periods[..] {
+            [fast, slow, signal] => Ok(MacdPeriods { fast, slow, signal }),
+            _ => Err(format!("expected periods like 12/26/9, got [{}]", s)),
+        }
+    }
+}
+
 pub struct MACDTrader<'a> {
     trading_fee: TradingFee,
     stake_size: StakeSize,
+    slippage: Slippage,
+    symbol_rules: Option<SymbolRules>,
     kline_feed: &'a mut dyn Iterator<Item = BinanceKline>,
     indicator: Box<dyn BinanceIndicatorInstance>,
 }
@@ -25,11 +89,14 @@ pub struct MACDTrader<'a> {
 impl<'a> MACDTrader<'a> {
     pub fn new(
         kline_feed: &'a mut dyn Iterator<Item = BinanceKline>,
+        periods: MacdPeriods,
         trading_fee: TradingFee,
         stake_size: StakeSize,
+        slippage: Slippage,
+        symbol_rules: Option<SymbolRules>,
     ) -> Self {
         debug!("creating a MACD Trader");
-        let macd = MACD::default();
+        let macd = periods.macd();
         let macd = macd
             .init(&kline_feed.next().unwrap())
             .expect("Unable to initialise MACD");
@@ -38,6 +105,8 @@ impl<'a> MACDTrader<'a> {
             kline_feed,
             indicator: Box::new(macd),    // TODO need to fix this over boxing
             trading_fee,
+            slippage,
+            symbol_rules,
             stake_size,
         }
     }
@@ -49,8 +118,16 @@ impl<'a> GenericTrader<'a> for MACDTrader<'a> {
         self.stake_size
     }
 
-    fn trading_fee(&self) -> TradingFee {
-        self.trading_fee
+    fn trading_fee(&self) -> &TradingFee {
+        &self.trading_fee
+    }
+
+    fn slippage(&self) -> Slippage {
+        self.slippage
+    }
+
+    fn symbol_rules(&self) -> Option<SymbolRules> {
+        self.symbol_rules
     }
 
     fn kline(&mut self) -> &mut dyn Iterator<Item = BinanceKline> {
@@ -1,11 +1,20 @@
 mod generic_trader;
-pub use generic_trader::{GenericTrader, StakeSize, TradingFee};
+pub use generic_trader::{GenericTrader, StakeSize};
+
+mod trading_fee;
+pub use trading_fee::{FeeTier, Liquidity, TradingFee};
+
+mod slippage;
+pub use slippage::Slippage;
 
 mod macd_trader;
-pub use macd_trader::MACDTrader;
+pub use macd_trader::{MACDTrader, MacdPeriods};
 
 mod hodl_trader;
 pub use hodl_trader::HODLTrader;
 
 mod dca_trader;
-pub use dca_trader::DCATrader;
\ No newline at end of file
+pub use dca_trader::DCATrader;
+
+mod risk_overlay;
+pub use risk_overlay::{RiskExit, RiskOverlay};
\ No newline at end of file
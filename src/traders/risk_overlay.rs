@@ -0,0 +1,277 @@
+use crate::account::Account;
+use crate::data::{BinanceKline, SymbolRules};
+use crate::indicators::BinanceIndicatorInstance;
+use crate::orders::{Fill, OrderBook};
+use crate::traders::{GenericTrader, Slippage, StakeSize, TradingFee};
+use chrono::{Duration, NaiveDateTime};
+use std::cell::Cell;
+use yata::core::Action;
+
+use log::debug;
+
+#[derive(Debug, Clone, Copy)]
+pub enum RiskExit {
+    /// exit once the close falls this fraction below the average cost
+    StopLoss(f64),
+    /// exit once the close rises this fraction above the average cost
+    TakeProfit(f64),
+    /// exit once the close falls this fraction below the highest high since entry
+    TrailingStop(f64),
+    /// exit once the close falls `multiplier` ATRs below the highest high since entry
+    AtrTrailingStop { period: usize, multiplier: f64 },
+    /// exit once the position has been held this long
+    TimeExit(Duration),
+}
+
+#[derive(Clone, Copy)]
+struct OpenTrade {
+    entry_time: NaiveDateTime,
+    highest: f64,
+}
+
+pub struct RiskOverlay<T> {
+    trader: T,
+    exits: Vec<RiskExit>,
+    /// set by the fill that opens a position, fills only see the account so this lives in a cell
+    open_trade: Cell<Option<OpenTrade>>,
+    atr: Option<f64>,
+    previous_close: Option<f64>,
+}
+
+impl<T> RiskOverlay<T> {
+    pub fn new(trader: T, exits: Vec<RiskExit>) -> Self {
+        Self {
+            trader,
+            exits,
+            open_trade: Cell::new(None),
+            atr: None,
+            previous_close: None,
+        }
+    }
+
+    fn update_atr(&mut self, kline: &BinanceKline) {
+        let true_range = match self.previous_close {
+            Some(close) => (kline.high - kline.low)
+                .max((kline.high - close).abs())
+                .max((kline.low - close).abs()),
+            None => kline.high - kline.low,
+        };
+        let period = self
+            .exits
+            .iter()
+            .find_map(|exit| match exit {
+                RiskExit::AtrTrailingStop { period, .. } => Some(*period),
+                _ => None,
+            })
+            .unwrap_or(14)
+            .max(1) as f64;
+        self.atr = Some(match self.atr {
+            Some(atr) => (atr * (period - 1.) + true_range) / period,
+            None => true_range,
+        });
+        self.previous_close = Some(kline.close);
+    }
+
+    /// start tracking a position opened by the last fill from its fill price, a fill at the open also sees the rest of the bar
+    fn track_entry(&self, was_flat: bool, timestamp: NaiveDateTime, kline: &BinanceKline, account: &Account) {
+        if !was_flat || account.position.quantity <= 0. {
+            return;
+        }
+        let price = match account.trade_history.last() {
+            Some(trade) => trade.price(),
+            None => return,
+        };
+        let highest = if timestamp == kline.start_time { price.max(kline.high) } else { price };
+        self.open_trade.set(Some(OpenTrade {
+            entry_time: timestamp,
+            highest,
+        }));
+    }
+
+    fn should_exit(&self, kline: &BinanceKline, cost: f64) -> Option<RiskExit> {
+        let open_trade = self.open_trade.get()?;
+        let atr = self.atr.unwrap_or(0.);
+        self.exits.iter().copied().find(|exit| match *exit {
+            RiskExit::StopLoss(pct) => kline.close <= cost * (1. - pct),
+            RiskExit::TakeProfit(pct) => kline.close >= cost * (1. + pct),
+            RiskExit::TrailingStop(pct) => kline.close <= open_trade.highest * (1. - pct),
+            RiskExit::AtrTrailingStop { multiplier, .. } => {
+                kline.close <= open_trade.highest - multiplier * atr
+            }
+            RiskExit::TimeExit(duration) => kline.end_time - open_trade.entry_time >= duration,
+        })
+    }
+}
+
+impl<'a, T> GenericTrader<'a> for RiskOverlay<T>
+where
+    T: GenericTrader<'a>,
+{
+    fn determine_trade(signals: &[Action]) -> Action {
+        T::determine_trade(signals)
+    }
+
+    fn stake_size(&self) -> StakeSize {
+        self.trader.stake_size()
+    }
+
+    fn trading_fee(&self) -> &TradingFee {
+        self.trader.trading_fee()
+    }
+
+    fn slippage(&self) -> Slippage {
+        self.trader.slippage()
+    }
+
+    fn symbol_rules(&self) -> Option<SymbolRules> {
+        self.trader.symbol_rules()
+    }
+
+    fn kline(&mut self) -> &mut dyn Iterator<Item = BinanceKline> {
+        self.trader.kline()
+    }
+
+    fn indicator(&mut self) -> &mut dyn BinanceIndicatorInstance {
+        self.trader.indicator()
+    }
+
+    fn execute(&self, action: Action, timestamp: NaiveDateTime, price: f64, kline: &BinanceKline, account: &mut Account) {
+        let was_flat = account.position.quantity <= 0.;
+        self.trader.execute(action, timestamp, price, kline, account);
+        self.track_entry(was_flat, timestamp, kline, account);
+    }
+
+    fn execute_order(&self, fill: &Fill, timestamp: NaiveDateTime, kline: &BinanceKline, account: &mut Account) {
+        let was_flat = account.position.quantity <= 0.;
+        self.trader.execute_order(fill, timestamp, kline, account);
+        self.track_entry(was_flat, timestamp, kline, account);
+    }
+
+    fn manage_orders(&mut self, kline: &BinanceKline, account: &Account, orders: &mut OrderBook) {
+        self.trader.manage_orders(kline, account, orders)
+    }
+
+    fn next_signal(&mut self, account: &Account) -> Option<(BinanceKline, Action)> {
+        let (kline, action) = self.trader.next_signal(account)?;
+        self.update_atr(&kline);
+
+        if account.position.quantity <= 0. {
+            self.open_trade.set(None);
+            return Some((kline, action));
+        }
+        // a position this overlay did not see open, such as a starting one, is tracked from here
+        let mut open_trade = self.open_trade.get().unwrap_or(OpenTrade {
+            entry_time: kline.start_time,
+            highest: kline.high,
+        });
+        open_trade.highest = open_trade.highest.max(kline.high);
+        self.open_trade.set(Some(open_trade));
+
+        match self.should_exit(&kline, account.position.cost) {
+            Some(exit) => {
+                debug!("{}, risk exit triggered by {:?}", kline.end_time, exit);
+                Some((kline, Action::Sell(1)))
+            }
+            None => Some((kline, action)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::account::{BuySellIndicator, Position};
+    use crate::backtest::{run_backtest, ExecutionTiming};
+    use crate::traders::HODLTrader;
+    use chrono::NaiveDate;
+
+    fn klines(closes: &[f64]) -> Vec<BinanceKline> {
+        let start = NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0);
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, close)| BinanceKline {
+                start_time: start + Duration::hours(i as i64),
+                open: *close,
+                high: *close,
+                low: *close,
+                close: *close,
+                volume: 1000.,
+                end_time: start + Duration::hours(i as i64 + 1) - Duration::seconds(1),
+            })
+            .collect()
+    }
+
+    fn run(closes: &[f64], exits: Vec<RiskExit>) -> Account {
+        run_klines(klines(closes), exits, ExecutionTiming::SameBarClose)
+    }
+
+    fn run_klines(klines: Vec<BinanceKline>, exits: Vec<RiskExit>, timing: ExecutionTiming) -> Account {
+        let start = klines[0].start_time;
+        let mut klines_iter = klines.into_iter();
+        let hodl = HODLTrader::new(&mut klines_iter, TradingFee::FixFee(0.), Slippage::Zero, None);
+        let mut trader = RiskOverlay::new(hodl, exits);
+        let position = Position { quantity: 0., cost: 0. };
+        let mut account = Account::new(1000., position, start);
+        run_backtest(&mut trader, &mut account, timing);
+        account.audit().expect("account does not reconcile");
+        account
+    }
+
+    #[test]
+    fn test_stop_loss() {
+        let account = run(&[100., 100., 95., 89.], vec![RiskExit::StopLoss(0.1)]);
+        assert_eq!(account.position.quantity, 0.);
+        assert_eq!(account.available_fund, 890.);
+    }
+
+    #[test]
+    fn test_take_profit() {
+        let account = run(&[100., 100., 105., 121.], vec![RiskExit::TakeProfit(0.2)]);
+        assert_eq!(account.position.quantity, 0.);
+        assert_eq!(account.available_fund, 1210.);
+    }
+
+    #[test]
+    fn test_trailing_stop() {
+        let exits = vec![RiskExit::StopLoss(0.5), RiskExit::TrailingStop(0.1)];
+        let account = run(&[100., 100., 150., 140., 130.], exits);
+        assert_eq!(account.position.quantity, 0.);
+        assert_eq!(account.available_fund, 1300.);
+    }
+
+    #[test]
+    fn test_trailing_stop_from_fill() {
+        let account = run(&[100., 100., 89.], vec![RiskExit::TrailingStop(0.1)]);
+        assert_eq!(account.position.quantity, 0.);
+        assert_eq!(account.available_fund, 890.);
+    }
+
+    #[test]
+    fn test_trailing_stop_sees_entry_bar() {
+        // bought at the open of the third bar, which then trades up to 150
+        let mut klines = klines(&[100., 100., 100., 134., 130.]);
+        klines[2].high = 150.;
+        klines[2].close = 140.;
+        let account = run_klines(klines, vec![RiskExit::TrailingStop(0.1)], ExecutionTiming::NextBarOpen(1));
+        assert_eq!(account.trade_history[0].price(), 100.);
+        assert_eq!(account.trade_history[1].buy_sell_indicator(), BuySellIndicator::Sell);
+        assert_eq!(account.trade_history[1].price(), 130.);
+    }
+
+    #[test]
+    fn test_time_exit() {
+        // held from the fill at the close of the second bar
+        let account = run(&[100., 100., 101., 102.], vec![RiskExit::TimeExit(Duration::hours(2))]);
+        assert_eq!(account.position.quantity, 0.);
+        assert_eq!(account.available_fund, 1020.);
+    }
+
+    #[test]
+    fn test_atr_trailing_stop() {
+        let exits = vec![RiskExit::AtrTrailingStop { period: 2, multiplier: 1. }];
+        let account = run(&[100., 100., 110., 120., 100.], exits);
+        assert_eq!(account.position.quantity, 0.);
+        assert_eq!(account.available_fund, 1000.);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::data::BinanceKline;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Slippage {
+    Zero,
+    /// a fixed cost in basis points of the quoted price
+    FixBps(f64),
+    /// a multiple of the candle's high-low range relative to its close
+    VolatilityScaled(f64),
+    /// an impact coefficient applied to the square root of the share of the candle's volume we trade
+    VolumeParticipation(f64),
+}
+
+impl Slippage {
+    pub fn rate(&self, quantity: f64, kline: &BinanceKline) -> f64 {
+        match *self {
+            Slippage::Zero => 0.,
+            Slippage::FixBps(bps) => bps / 10_000.,
+            Slippage::VolatilityScaled(factor) => {
+                if kline.close > 0. {
+                    factor * (kline.high - kline.low) / kline.close
+                } else {
+                    0.
+                }
+            }
+            Slippage::VolumeParticipation(impact) => {
+                if kline.volume > 0. {
+                    impact * (quantity / kline.volume).sqrt()
+                } else {
+                    0.
+                }
+            }
+        }
+    }
+
+    pub fn buy_price(&self, price: f64, quantity: f64, kline: &BinanceKline) -> f64 {
+        price * (1. + self.rate(quantity, kline))
+    }
+
+    pub fn sell_price(&self, price: f64, quantity: f64, kline: &BinanceKline) -> f64 {
+        price * (1. - self.rate(quantity, kline)).max(0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn kline() -> BinanceKline {
+        let timestamp = NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0);
+        BinanceKline {
+            start_time: timestamp,
+            open: 100.,
+            high: 110.,
+            low: 90.,
+            close: 100.,
+            volume: 400.,
+            end_time: timestamp,
+        }
+    }
+
+    #[test]
+    fn test_no_slippage() {
+        assert_eq!(Slippage::Zero.buy_price(100., 1., &kline()), 100.);
+        assert_eq!(Slippage::Zero.sell_price(100., 1., &kline()), 100.);
+    }
+
+    #[test]
+    fn test_fix_bps() {
+        let slippage = Slippage::FixBps(50.);
+        assert!((slippage.buy_price(100., 1., &kline()) - 100.5).abs() < 1e-9);
+        assert!((slippage.sell_price(100., 1., &kline()) - 99.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_volatility_scaled() {
+        let slippage = Slippage::VolatilityScaled(0.1);
+        assert!((slippage.rate(1., &kline()) - 0.02).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_volume_participation() {
+        let slippage = Slippage::VolumeParticipation(0.1);
+        assert!((slippage.rate(4., &kline()) - 0.01).abs() < 1e-12);
+        assert!(slippage.rate(16., &kline()) > slippage.rate(4., &kline()));
+    }
+}
@@ -0,0 +1,111 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+}
+
+/// a VIP level that applies once the rolling 30-day volume reaches `min_volume`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeTier {
+    pub min_volume: f64,
+    pub maker: f64,
+    pub taker: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TradingFee {
+    FixFee(f64),
+    PercentageFee(f64),
+    MakerTaker { maker: f64, taker: f64 },
+    /// tiers sorted by `min_volume`, the highest reached tier applies
+    Tiered(Vec<FeeTier>),
+}
+
+impl TradingFee {
+    /// the percentage charged on a trade, or `None` for a fixed fee
+    pub fn rate(&self, liquidity: Liquidity, volume_30d: f64) -> Option<f64> {
+        let pick = |maker: f64, taker: f64| match liquidity {
+            Liquidity::Maker => maker,
+            Liquidity::Taker => taker,
+        };
+        match self {
+            TradingFee::FixFee(_) => None,
+            TradingFee::PercentageFee(pct) => Some(*pct),
+            TradingFee::MakerTaker { maker, taker } => Some(pick(*maker, *taker)),
+            TradingFee::Tiered(tiers) => Some(
+                tiers
+                    .iter()
+                    .take_while(|tier| tier.min_volume <= volume_30d)
+                    .last()
+                    .or_else(|| tiers.first())
+                    .map_or(0., |tier| pick(tier.maker, tier.taker)),
+            ),
+        }
+    }
+
+    pub fn fee(&self, notional: f64, liquidity: Liquidity, volume_30d: f64) -> f64 {
+        match self {
+            TradingFee::FixFee(fee) => *fee,
+            _ => notional * self.rate(liquidity, volume_30d).unwrap_or(0.),
+        }
+    }
+
+    /// the notional that `amount` buys when the fee is paid out of the same amount
+    pub fn notional_for(&self, amount: f64, liquidity: Liquidity, volume_30d: f64) -> f64 {
+        match self {
+            TradingFee::FixFee(fee) => (amount - fee).max(0.),
+            _ => amount / (1. + self.rate(liquidity, volume_30d).unwrap_or(0.)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vip_schedule() -> TradingFee {
+        TradingFee::Tiered(vec![
+            FeeTier {
+                min_volume: 0.,
+                maker: 0.001,
+                taker: 0.001,
+            },
+            FeeTier {
+                min_volume: 1_000_000.,
+                maker: 0.0009,
+                taker: 0.001,
+            },
+            FeeTier {
+                min_volume: 5_000_000.,
+                maker: 0.0008,
+                taker: 0.0009,
+            },
+        ])
+    }
+
+    #[test]
+    fn test_fix_and_percentage_fee() {
+        assert_eq!(TradingFee::FixFee(1.).fee(500., Liquidity::Taker, 0.), 1.);
+        assert_eq!(TradingFee::PercentageFee(0.01).fee(500., Liquidity::Maker, 0.), 5.);
+        assert_eq!(TradingFee::FixFee(1.).notional_for(500., Liquidity::Taker, 0.), 499.);
+        assert_eq!(TradingFee::PercentageFee(0.01).notional_for(505., Liquidity::Taker, 0.), 500.);
+    }
+
+    #[test]
+    fn test_maker_taker() {
+        let fee = TradingFee::MakerTaker {
+            maker: 0.0002,
+            taker: 0.0004,
+        };
+        assert_eq!(fee.rate(Liquidity::Maker, 0.), Some(0.0002));
+        assert_eq!(fee.rate(Liquidity::Taker, 0.), Some(0.0004));
+    }
+
+    #[test]
+    fn test_tiered() {
+        let fee = vip_schedule();
+        assert_eq!(fee.rate(Liquidity::Maker, 10.), Some(0.001));
+        assert_eq!(fee.rate(Liquidity::Maker, 1_000_000.), Some(0.0009));
+        assert_eq!(fee.rate(Liquidity::Taker, 7_000_000.), Some(0.0009));
+    }
+}
@@ -0,0 +1,332 @@
+mod ui;
+
+use crate::account::{Account, EquityPoint};
+use crate::config::StrategyConfig;
+use crate::data::BinanceKline;
+use crate::indicators::{macd_series, macd_warm_up};
+use crate::metrics::growth_index;
+use crate::traders::MacdPeriods;
+use chrono::NaiveDateTime;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use std::io;
+use std::ops::Range;
+
+/// bars shown when the view opens, `+` and `-` halve and double it
+const DEFAULT_ZOOM: usize = 120;
+const MIN_ZOOM: usize = 10;
+
+/// the MACD a run traded on, line and signal for every bar
+pub struct Macd {
+    pub periods: MacdPeriods,
+    /// from the second bar on, the first only initialises the indicator
+    values: Vec<(f64, f64)>,
+    /// bars before the MACD is worth drawing
+    warm_up: usize,
+}
+
+/// one strategy's run, with its growth index kept alongside so the cursor can read returns directly
+pub struct Run {
+    pub name: String,
+    pub account: Account,
+    growth: Vec<f64>,
+    /// none for the strategies that do not trade on a MACD
+    macd: Option<Macd>,
+}
+
+impl Run {
+    pub fn new(strategy: &StrategyConfig, account: Account, klines: &[BinanceKline]) -> Self {
+        let growth = growth_index(&account).into_iter().map(|(_, value)| value).collect();
+        let macd = strategy.macd().map(|periods| Macd {
+            periods,
+            values: macd_series(klines, periods)
+                .into_iter()
+                .map(|(_, line, signal)| (line, signal))
+                .collect(),
+            warm_up: macd_warm_up(periods),
+        });
+        Self {
+            name: strategy.name(),
+            account,
+            growth,
+            macd,
+        }
+    }
+}
+
+/// every run on one symbol and the klines they were run on
+pub struct SymbolRuns {
+    pub symbol: String,
+    pub klines: Vec<BinanceKline>,
+    pub runs: Vec<Run>,
+}
+
+impl SymbolRuns {
+    pub fn new(symbol: &str, klines: Vec<BinanceKline>, runs: Vec<(StrategyConfig, Account)>) -> Self {
+        let runs = runs
+            .into_iter()
+            .map(|(strategy, account)| Run::new(&strategy, account, &klines))
+            .collect();
+        Self {
+            symbol: symbol.to_string(),
+            klines,
+            runs,
+        }
+    }
+
+    /// the bar a timestamp falls in
+    fn bar_at(&self, timestamp: NaiveDateTime) -> usize {
+        self.klines
+            .partition_point(|kline| kline.start_time <= timestamp)
+            .saturating_sub(1)
+    }
+}
+
+/// a run as it stood at the close of the cursor's bar
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub point: EquityPoint,
+    /// time-weighted since the start
+    pub return_pct: f64,
+    pub drawdown: f64,
+}
+
+pub struct App {
+    symbols: Vec<SymbolRuns>,
+    symbol: usize,
+    cursor: usize,
+    zoom: usize,
+    run: usize,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(symbols: Vec<SymbolRuns>) -> Self {
+        let cursor = symbols.first().map_or(0, |symbol| symbol.klines.len().saturating_sub(1));
+        Self {
+            symbols,
+            symbol: 0,
+            cursor,
+            zoom: DEFAULT_ZOOM,
+            run: 0,
+            quit: false,
+        }
+    }
+
+    fn current(&self) -> &SymbolRuns {
+        &self.symbols[self.symbol]
+    }
+
+    fn cursor_kline(&self) -> &BinanceKline {
+        &self.current().klines[self.cursor]
+    }
+
+    fn selected_run(&self) -> Option<&Run> {
+        self.current().runs.get(self.run)
+    }
+
+    /// the bars on screen, keeping the cursor in the middle where the data allows
+    fn window(&self) -> Range<usize> {
+        let len = self.current().klines.len();
+        let zoom = self.zoom.min(len);
+        let start = self.cursor.saturating_sub(zoom / 2).min(len - zoom);
+        start..start + zoom
+    }
+
+    fn move_cursor(&mut self, bars: isize) {
+        let last = self.current().klines.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(bars).min(last);
+    }
+
+    fn snapshot(&self, run: &Run) -> Snapshot {
+        let curve = run.account.equity_curve();
+        let close = self.cursor_kline().end_time;
+        let index = curve.partition_point(|point| point.timestamp <= close).saturating_sub(1);
+        let peak = run.growth[..=index].iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Snapshot {
+            point: curve[index],
+            return_pct: run.growth[index] - 1.,
+            drawdown: run.growth[index] / peak - 1.,
+        }
+    }
+
+    /// index of the selected run's last trade at or before the cursor
+    fn trade_at_cursor(&self) -> Option<usize> {
+        let trades = &self.selected_run()?.account.trade_history;
+        let close = self.cursor_kline().end_time;
+        trades.partition_point(|trade| trade.timestamp() <= close).checked_sub(1)
+    }
+
+    fn jump_to(&mut self, timestamp: NaiveDateTime) {
+        self.cursor = self.current().bar_at(timestamp);
+    }
+
+    fn next_trade(&mut self) {
+        let next = match self.trade_at_cursor() {
+            Some(index) => index + 1,
+            None => 0,
+        };
+        let trades = self.selected_run().map(|run| &run.account.trade_history);
+        if let Some(timestamp) = trades.and_then(|trades| trades.get(next)).map(|trade| trade.timestamp()) {
+            self.jump_to(timestamp);
+        }
+    }
+
+    fn previous_trade(&mut self) {
+        let current = match self.trade_at_cursor() {
+            Some(index) => index,
+            None => return,
+        };
+        let trades = &self.selected_run().unwrap().account.trade_history;
+        // a trade on the cursor's own bar counts as the current one, step past it
+        let previous = if self.current().bar_at(trades[current].timestamp()) == self.cursor {
+            current.checked_sub(1)
+        } else {
+            Some(current)
+        };
+        if let Some(timestamp) = previous.map(|previous| trades[previous].timestamp()) {
+            self.jump_to(timestamp);
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) {
+        let page = self.window().len() as isize;
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(-1),
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(1),
+            KeyCode::PageUp => self.move_cursor(-page),
+            KeyCode::PageDown => self.move_cursor(page),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.move_cursor(isize::MAX),
+            KeyCode::Char('+') => self.zoom = (self.zoom / 2).max(MIN_ZOOM),
+            KeyCode::Char('-') => self.zoom = (self.zoom * 2).min(self.current().klines.len().max(MIN_ZOOM)),
+            KeyCode::Tab => self.run = (self.run + 1) % self.current().runs.len().max(1),
+            KeyCode::BackTab => {
+                let runs = self.current().runs.len().max(1);
+                self.run = (self.run + runs - 1) % runs;
+            }
+            KeyCode::Char('n') => self.next_trade(),
+            KeyCode::Char('p') => self.previous_trade(),
+            KeyCode::Char('s') => {
+                self.symbol = (self.symbol + 1) % self.symbols.len();
+                self.run = 0;
+                self.cursor = self.current().klines.len().saturating_sub(1);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// open the explorer on the runs and block until it is closed
+pub fn explore(symbols: Vec<SymbolRuns>) -> io::Result<()> {
+    let symbols: Vec<SymbolRuns> = symbols.into_iter().filter(|symbol| !symbol.klines.is_empty()).collect();
+    if symbols.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "nothing to explore, no klines were loaded"));
+    }
+    let mut app = App::new(symbols);
+    let mut terminal = ratatui::init();
+    let result = (|| {
+        while !app.quit {
+            terminal.draw(|frame| ui::draw(frame, &app))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key.code);
+                }
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines;
+    use crate::strategy::{run_strategy, Strategy, StrategySettings};
+    use chrono::NaiveDate;
+
+    fn klines() -> Vec<BinanceKline> {
+        test_klines(NaiveDate::from_ymd(2021, 11, 1).and_hms(0, 0, 0), 24 * 30, 0.)
+    }
+
+    pub(super) fn app() -> App {
+        let klines = klines();
+        let settings = StrategySettings::default();
+        let runs = [Strategy::Macd, Strategy::Hodl, Strategy::Dca]
+            .iter()
+            .map(|strategy| (StrategyConfig::from_strategy(*strategy), run_strategy(*strategy, &klines, &settings)))
+            .collect();
+        App::new(vec![SymbolRuns::new("ETHUSDT", klines, runs)])
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut app = app();
+        assert_eq!(app.cursor, 24 * 30 - 1);
+        assert_eq!(app.window(), 24 * 30 - DEFAULT_ZOOM..24 * 30);
+
+        app.handle_key(KeyCode::Home);
+        assert_eq!(app.cursor, 0);
+        assert_eq!(app.window(), 0..DEFAULT_ZOOM);
+        app.handle_key(KeyCode::Left);
+        assert_eq!(app.cursor, 0);
+        app.handle_key(KeyCode::PageDown);
+        assert_eq!(app.cursor, DEFAULT_ZOOM);
+        assert_eq!(app.window(), DEFAULT_ZOOM / 2..DEFAULT_ZOOM * 3 / 2);
+
+        app.handle_key(KeyCode::Char('+'));
+        assert_eq!(app.window().len(), DEFAULT_ZOOM / 2);
+        app.handle_key(KeyCode::End);
+        assert_eq!(app.cursor, 24 * 30 - 1);
+
+        app.handle_key(KeyCode::BackTab);
+        assert_eq!(app.selected_run().unwrap().name, "DCA");
+        app.handle_key(KeyCode::Tab);
+        assert_eq!(app.selected_run().unwrap().name, "MACD");
+        app.handle_key(KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn test_trade_navigation() {
+        let mut app = app();
+        let run = app.selected_run().unwrap();
+        let trades: Vec<usize> = run
+            .account
+            .trade_history
+            .iter()
+            .map(|trade| app.current().bar_at(trade.timestamp()))
+            .collect();
+        assert!(trades.len() > 2);
+
+        app.handle_key(KeyCode::Home);
+        app.handle_key(KeyCode::Char('n'));
+        assert_eq!(app.cursor, trades[0]);
+        assert_eq!(app.trade_at_cursor(), Some(0));
+        app.handle_key(KeyCode::Char('n'));
+        assert_eq!(app.cursor, trades[1]);
+        app.handle_key(KeyCode::Char('p'));
+        assert_eq!(app.cursor, trades[0]);
+        app.handle_key(KeyCode::Char('p'));
+        assert_eq!(app.cursor, trades[0]);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut app = app();
+        app.handle_key(KeyCode::Home);
+        for run in app.current().runs.iter() {
+            let snapshot = app.snapshot(run);
+            assert!(snapshot.point.timestamp <= app.cursor_kline().end_time);
+            assert_eq!(snapshot.drawdown, 0.);
+        }
+        app.handle_key(KeyCode::End);
+        for run in app.current().runs.iter() {
+            let snapshot = app.snapshot(run);
+            assert_eq!(snapshot.point.equity, run.account.equity());
+            assert!(snapshot.drawdown <= 0.);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use super::{App, Macd};
+use crate::account::BuySellIndicator;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::canvas::{Canvas, Line as CanvasLine};
+use ratatui::widgets::{Axis, Block, Chart, Dataset, GraphType, List, ListItem, ListState, Row, Table, TableState};
+use ratatui::Frame;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+const HELP: &str = " ←/→ bar  PgUp/PgDn page  Home/End  +/- zoom  n/p trade  Tab run  s symbol  q quit ";
+/// how much of the space between two bars a candle body takes
+const BODY_WIDTH: f64 = 0.6;
+
+fn bounds(values: impl Iterator<Item = f64>) -> [f64; 2] {
+    let (low, high) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+        (low.min(value), high.max(value))
+    });
+    if low > high {
+        return [0., 1.];
+    }
+    let pad = ((high - low) * 0.05).max(1e-9);
+    [low - pad, high + pad]
+}
+
+/// offsets from a bar's centre, one per braille dot column its body covers when `bars` share `width` cells
+fn body_offsets(width: u16, bars: usize) -> Vec<f64> {
+    // a braille cell is two dots wide
+    let dots_per_bar = f64::from(width) * 2. / bars.max(1) as f64;
+    let half = (dots_per_bar * BODY_WIDTH / 2.).floor() as i32;
+    (-half..=half).map(|dot| f64::from(dot) / dots_per_bar).collect()
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [header, body, footer] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [charts, side] = Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(body);
+    let macd = app.selected_run().and_then(|run| run.macd.as_ref());
+    let [runs, trades] = Layout::vertical([Constraint::Length(app.current().runs.len() as u16 + 3), Constraint::Min(0)])
+        .areas(side);
+
+    let kline = app.cursor_kline();
+    let title = Line::from(vec![
+        Span::from(format!(" {} ", app.current().symbol)).bold(),
+        Span::from(format!(
+            "({}/{})  {}  O {:.2} H {:.2} L {:.2} C {:.2}  V {:.0}",
+            app.symbol + 1,
+            app.symbols.len(),
+            kline.start_time.format(TIME_FORMAT),
+            kline.open,
+            kline.high,
+            kline.low,
+            kline.close,
+            kline.volume
+        )),
+    ]);
+    frame.render_widget(title, header);
+    frame.render_widget(Line::from(HELP).dark_gray(), footer);
+
+    // only runs trading on a MACD get the MACD pane
+    match macd {
+        Some(macd) => {
+            let [candles, indicator] =
+                Layout::vertical([Constraint::Percentage(70), Constraint::Percentage(30)]).areas(charts);
+            draw_candles(frame, app, candles);
+            draw_macd(frame, app, macd, indicator);
+        }
+        None => draw_candles(frame, app, charts),
+    }
+    draw_runs(frame, app, runs);
+    draw_trades(frame, app, trades);
+}
+
+/// a candle per bar coloured by its direction, the open to close body over the high to low wick,
+/// the selected run's trades and the cursor on top
+fn draw_candles(frame: &mut Frame, app: &App, area: Rect) {
+    let window = app.window();
+    let klines = &app.current().klines[window.clone()];
+    let y_bounds = bounds(klines.iter().flat_map(|kline| [kline.low, kline.high]));
+    let body = body_offsets(area.width.saturating_sub(2), window.len());
+    let run = app.selected_run();
+    let canvas = Canvas::default()
+        .block(Block::bordered().title(format!(" price, trades of {} ", run.map_or("-", |run| &run.name))))
+        .marker(Marker::Braille)
+        .x_bounds([window.start as f64, window.end as f64])
+        .y_bounds(y_bounds)
+        .paint(|ctx| {
+            for (i, kline) in klines.iter().enumerate() {
+                let x = (window.start + i) as f64;
+                let colour = if kline.close >= kline.open { Color::Green } else { Color::Red };
+                ctx.draw(&CanvasLine::new(x, kline.low, x, kline.high, colour));
+                for offset in &body {
+                    ctx.draw(&CanvasLine::new(x + offset, kline.open, x + offset, kline.close, colour));
+                }
+            }
+            let cursor = app.cursor as f64;
+            ctx.draw(&CanvasLine::new(cursor, y_bounds[0], cursor, y_bounds[1], Color::Yellow));
+            ctx.layer();
+            let trades = run.map(|run| run.account.trade_history.as_slice()).unwrap_or_default();
+            for trade in trades {
+                let bar = app.current().bar_at(trade.timestamp());
+                if window.contains(&bar) {
+                    let marker = match trade.buy_sell_indicator() {
+                        BuySellIndicator::Buy => Span::from("▲").light_blue().bold(),
+                        BuySellIndicator::Sell => Span::from("▼").light_magenta().bold(),
+                    };
+                    ctx.print(bar as f64, trade.price(), marker);
+                }
+            }
+        });
+    frame.render_widget(canvas, area);
+}
+
+/// the selected run's MACD and signal lines, left blank over the bars the indicator is still warming up on
+fn draw_macd(frame: &mut Frame, app: &App, macd: &Macd, area: Rect) {
+    let window = app.window();
+    let from = window.start.max(macd.warm_up).min(window.end);
+    let values = &macd.values[from.saturating_sub(1)..window.end.saturating_sub(1)];
+    let line: Vec<(f64, f64)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, (line, _))| ((from + i) as f64, *line))
+        .collect();
+    let signal: Vec<(f64, f64)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, (_, signal))| ((from + i) as f64, *signal))
+        .collect();
+    let y_bounds = bounds(values.iter().flat_map(|(line, signal)| [*line, *signal]));
+    let cursor = [(app.cursor as f64, y_bounds[0]), (app.cursor as f64, y_bounds[1])];
+    let title = if app.cursor < macd.warm_up {
+        format!(" MACD {} warming up ", macd.periods)
+    } else {
+        let (line_value, signal_value) = macd.values[app.cursor - 1];
+        format!(
+            " MACD {} {:.2}  signal {:.2}  histogram {:.2} ",
+            macd.periods,
+            line_value,
+            signal_value,
+            line_value - signal_value
+        )
+    };
+    let datasets = vec![
+        Dataset::default()
+            .name("MACD")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .cyan()
+            .data(&line),
+        Dataset::default()
+            .name("signal")
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .magenta()
+            .data(&signal),
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .yellow()
+            .data(&cursor),
+    ];
+    let chart = Chart::new(datasets)
+        .block(Block::bordered().title(title))
+        .x_axis(Axis::default().bounds([window.start as f64, window.end as f64]))
+        .y_axis(Axis::default().bounds(y_bounds))
+        .hidden_legend_constraints((Constraint::Ratio(1, 1), Constraint::Ratio(1, 1)));
+    frame.render_widget(chart, area);
+}
+
+/// every run side by side as it stood at the cursor
+fn draw_runs(frame: &mut Frame, app: &App, area: Rect) {
+    let rows: Vec<Row> = app
+        .current()
+        .runs
+        .iter()
+        .map(|run| {
+            let snapshot = app.snapshot(run);
+            Row::new(vec![
+                run.name.clone(),
+                format!("{:.4}", snapshot.point.quantity),
+                format!("{:.2}", snapshot.point.cash),
+                format!("{:.2}", snapshot.point.equity),
+                format!("{:+.2}%", snapshot.return_pct * 100.),
+                format!("{:.2}%", snapshot.drawdown * 100.),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(9),
+        Constraint::Length(8),
+    ];
+    let table = Table::new(rows, widths)
+        .header(Row::new(vec!["run", "position", "cash", "equity", "return", "dd"]).bold())
+        .block(Block::bordered().title(" at cursor "))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = TableState::default().with_selected(Some(app.run));
+    frame.render_stateful_widget(table, area, &mut state);
+}
+
+/// the selected run's trades, with the last one at or before the cursor highlighted
+fn draw_trades(frame: &mut Frame, app: &App, area: Rect) {
+    let trades = app.selected_run().map(|run| run.account.trade_history.as_slice()).unwrap_or_default();
+    let items: Vec<ListItem> = trades
+        .iter()
+        .map(|trade| {
+            let side = match trade.buy_sell_indicator() {
+                BuySellIndicator::Buy => Span::from("BUY ").light_blue(),
+                BuySellIndicator::Sell => Span::from("SELL").light_magenta(),
+            };
+            ListItem::new(Line::from(vec![
+                Span::from(format!("{} ", trade.timestamp().format(TIME_FORMAT))),
+                side,
+                Span::from(format!(" {:.4} @ {:.2}", trade.quantity(), trade.price())),
+            ]))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(" {} trades ", trades.len())))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(app.trade_at_cursor());
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::app;
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyCode;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_draw() {
+        let mut app = app();
+        app.handle_key(KeyCode::Home);
+        app.handle_key(KeyCode::Char('n'));
+        let mut terminal = Terminal::new(TestBackend::new(160, 48)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        for text in ["ETHUSDT", "(1/1)", "at cursor", "MACD", "HODL", "DCA", "BUY", "trades of MACD"] {
+            assert!(screen.contains(text), "[{}] is not on screen", text);
+        }
+    }
+
+    #[test]
+    fn test_body_offsets() {
+        // zoomed out the body is no wider than the wick
+        assert_eq!(body_offsets(100, 120), vec![0.]);
+        // ten bars over a hundred cells leave twenty dots a bar, twelve of them for the body
+        let offsets = body_offsets(100, 10);
+        assert_eq!(offsets.len(), 13);
+        assert_eq!(offsets[0], -0.3);
+        assert_eq!(offsets[12], 0.3);
+    }
+
+    #[test]
+    fn test_macd_warm_up() {
+        let mut app = app();
+        app.handle_key(KeyCode::Home);
+        let mut terminal = Terminal::new(TestBackend::new(160, 48)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("MACD 12/26/9 warming up"));
+
+        // the last bar reads the last value of a series that starts at the second bar
+        app.handle_key(KeyCode::End);
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("histogram"));
+    }
+
+    #[test]
+    fn test_no_macd_pane() {
+        let mut app = app();
+        // HODL is the second run
+        app.handle_key(KeyCode::Tab);
+        let mut terminal = Terminal::new(TestBackend::new(160, 48)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("trades of HODL"));
+        assert!(!screen.contains("histogram"));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::comparison::Dataset;
+use crate::config::{Period, StrategyConfig};
+use crate::grid::{best, grid_search, GridResult};
+use crate::metrics::{growth_index, Performance, RiskRatios};
+use crate::report::{ComparisonRow, RankBy};
+use crate::strategy::run_strategy;
+use chrono::{Duration, NaiveDateTime};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+/// a growth index or compounded capital over time
+type Curve = Vec<(NaiveDateTime, f64)>;
+
+fn default_in_sample_days() -> i64 {
+    180
+}
+
+fn default_out_of_sample_days() -> i64 {
+    60
+}
+
+/// rolling windows, parameters are picked on `in_sample_days` and then traded on the `out_of_sample_days` after them
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WalkForwardConfig {
+    #[serde(default = "default_in_sample_days")]
+    pub in_sample_days: i64,
+    #[serde(default = "default_out_of_sample_days")]
+    pub out_of_sample_days: i64,
+}
+
+impl Default for WalkForwardConfig {
+    fn default() -> Self {
+        Self {
+            in_sample_days: default_in_sample_days(),
+            out_of_sample_days: default_out_of_sample_days(),
+        }
+    }
+}
+
+impl WalkForwardConfig {
+    /// in-sample and out-of-sample pairs over `period`, stepping by the out-of-sample length so the out-of-sample
+    /// periods follow each other, the last one is cut short at the end of `period`
+    pub fn windows(&self, period: Period) -> Vec<(Period, Period)> {
+        let mut windows = Vec::new();
+        if self.in_sample_days <= 0 || self.out_of_sample_days <= 0 {
+            return windows;
+        }
+        let mut from = period.from;
+        loop {
+            let split = from + Duration::days(self.in_sample_days);
+            if split >= period.to {
+                return windows;
+            }
+            let to = (split + Duration::days(self.out_of_sample_days)).min(period.to);
+            windows.push((Period { from, to: split }, Period { from: split, to }));
+            from += Duration::days(self.out_of_sample_days);
+        }
+    }
+}
+
+/// one window, the parameters that did best in sample and how they did out of sample
+#[derive(Debug, Clone)]
+pub struct WalkForwardWindow {
+    pub in_sample: Period,
+    pub out_of_sample: Period,
+    /// the best grid result in sample
+    pub chosen: GridResult,
+    pub out_of_sample_row: ComparisonRow,
+}
+
+/// a walk-forward over one symbol and period
+#[derive(Debug, Clone)]
+pub struct WalkForward {
+    pub symbol: String,
+    pub period: Period,
+    /// windows without klines on either side are left out
+    pub windows: Vec<WalkForwardWindow>,
+    /// the starting capital compounded by the out-of-sample growth indices one after the other. notional, every
+    /// out-of-sample run starts again from the starting capital and its deposits and withdrawals are chained out
+    pub compounded_capital: Curve,
+    pub performance: Option<Performance>,
+    pub ratios: Option<RiskRatios>,
+}
+
+/// growth indices laid end to end, each continuing from where the previous one finished
+fn stitch(segments: &[Curve]) -> Curve {
+    let mut curve: Curve = Vec::new();
+    let mut level = 1.;
+    for segment in segments {
+        for &(timestamp, value) in segment {
+            // a segment opens where the last one closed, keep one point for it
+            if curve.last().is_some_and(|(last, _)| *last >= timestamp) {
+                continue;
+            }
+            curve.push((timestamp, level * value));
+        }
+        if let Some((_, value)) = segment.last() {
+            level *= value;
+        }
+    }
+    curve
+}
+
+/// optimise every strategy in each in-sample window by `objective` and trade the winner out of sample,
+/// the grid searches and the out-of-sample runs are spread over the rayon pool. every out-of-sample run starts
+/// flat with fresh indicators, so the first bars of each window are spent warming up
+pub fn walk_forward(
+    datasets: &[Dataset],
+    strategies: &[StrategyConfig],
+    config: WalkForwardConfig,
+    objective: RankBy,
+    risk_free_rate: f64,
+) -> Vec<WalkForward> {
+    let windows: Vec<(usize, Period, Period)> = datasets
+        .iter()
+        .enumerate()
+        .flat_map(|(i, dataset)| {
+            config
+                .windows(dataset.period)
+                .into_iter()
+                .map(move |(in_sample, out_of_sample)| (i, in_sample, out_of_sample))
+        })
+        .collect();
+    let slice = |i: usize, period: Period| {
+        let dataset = &datasets[i];
+        Dataset::new(&dataset.symbol, period, &dataset.klines, dataset.settings.clone())
+    };
+    let in_sample: Vec<Dataset> = windows.iter().map(|(i, period, _)| slice(*i, *period)).collect();
+    let searched = grid_search(&in_sample, strategies, risk_free_rate);
+
+    let out_of_sample: Vec<(usize, WalkForwardWindow, Curve)> = windows
+        .par_iter()
+        .zip(searched.par_iter())
+        .filter_map(|((i, in_sample, out_of_sample), results)| {
+            let chosen = best(results, objective)?.clone();
+            let dataset = slice(*i, *out_of_sample);
+            if dataset.klines.is_empty() {
+                return None;
+            }
+            let strategy = &chosen.strategy;
+            let account = run_strategy(strategy.strategy(), &dataset.klines, &strategy.settings(&dataset.settings));
+            let row = ComparisonRow::new(
+                &dataset.symbol,
+                &out_of_sample.to_string(),
+                &strategy.name(),
+                &account,
+                risk_free_rate,
+            );
+            let window = WalkForwardWindow {
+                in_sample: *in_sample,
+                out_of_sample: *out_of_sample,
+                chosen,
+                out_of_sample_row: row,
+            };
+            Some((*i, window, growth_index(&account)))
+        })
+        .collect();
+
+    datasets
+        .iter()
+        .enumerate()
+        .map(|(i, dataset)| {
+            let (windows, segments): (Vec<WalkForwardWindow>, Vec<Curve>) = out_of_sample
+                .iter()
+                .filter(|(index, _, _)| *index == i)
+                .map(|(_, window, growth)| (window.clone(), growth.clone()))
+                .unzip();
+            let growth = stitch(&segments);
+            let capital = dataset.settings.capital;
+            WalkForward {
+                symbol: dataset.symbol.clone(),
+                period: dataset.period,
+                windows,
+                compounded_capital: growth.iter().map(|(timestamp, value)| (*timestamp, value * capital)).collect(),
+                performance: Performance::from_growth(&growth),
+                ratios: RiskRatios::from_growth(&growth, risk_free_rate),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::test_klines;
+    use crate::grid::{GridConfig, MacdGrid};
+    use crate::strategy::StrategySettings;
+    use chrono::NaiveDate;
+
+    fn period(from: (i32, u32, u32), to: (i32, u32, u32)) -> Period {
+        Period {
+            from: NaiveDate::from_ymd(from.0, from.1, from.2),
+            to: NaiveDate::from_ymd(to.0, to.1, to.2),
+        }
+    }
+
+    #[test]
+    fn test_windows() {
+        let config = WalkForwardConfig {
+            in_sample_days: 30,
+            out_of_sample_days: 10,
+        };
+        let windows = config.windows(period((2021, 1, 1), (2021, 2, 25)));
+        assert_eq!(
+            windows,
+            vec![
+                (period((2021, 1, 1), (2021, 1, 31)), period((2021, 1, 31), (2021, 2, 10))),
+                (period((2021, 1, 11), (2021, 2, 10)), period((2021, 2, 10), (2021, 2, 20))),
+                (period((2021, 1, 21), (2021, 2, 20)), period((2021, 2, 20), (2021, 2, 25))),
+            ]
+        );
+        assert!(config.windows(period((2021, 1, 1), (2021, 1, 31))).is_empty());
+    }
+
+    #[test]
+    fn test_stitch() {
+        let t = |hour| NaiveDate::from_ymd(2021, 1, 1).and_hms(hour, 0, 0);
+        let segments = vec![
+            vec![(t(0), 1.), (t(1), 1.1), (t(2), 1.2)],
+            vec![(t(2), 1.), (t(3), 0.5)],
+            vec![(t(4), 1.), (t(5), 2.)],
+        ];
+        let curve = stitch(&segments);
+        let expected = [(t(0), 1.), (t(1), 1.1), (t(2), 1.2), (t(3), 0.6), (t(4), 0.6), (t(5), 1.2)];
+        assert_eq!(curve.len(), expected.len());
+        for ((timestamp, value), (expected_timestamp, expected_value)) in curve.iter().zip(expected.iter()) {
+            assert_eq!(timestamp, expected_timestamp);
+            assert!((value - expected_value).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_walk_forward() {
+        let klines = test_klines(NaiveDate::from_ymd(2021, 1, 1).and_hms(0, 0, 0), 24 * 90, 0.01);
+        let datasets = vec![Dataset::new(
+            "ETHUSDT",
+            period((2021, 1, 1), (2021, 4, 1)),
+            &klines,
+            StrategySettings::default(),
+        )];
+        let grid = GridConfig::Macd {
+            macd: MacdGrid {
+                fast: vec![6, 12],
+                ..MacdGrid::default()
+            },
+            stake: vec![0.5, 1.],
+        };
+        let config = WalkForwardConfig {
+            in_sample_days: 30,
+            out_of_sample_days: 15,
+        };
+        let walks = walk_forward(&datasets, &grid.strategies(), config, RankBy::Return, 0.);
+        assert_eq!(walks.len(), 1);
+        let walk = &walks[0];
+        assert_eq!(walk.windows.len(), 4);
+        for (window, (in_sample, out_of_sample)) in walk.windows.iter().zip(config.windows(walk.period)) {
+            assert_eq!(window.in_sample, in_sample);
+            assert_eq!(window.out_of_sample, out_of_sample);
+            assert_eq!(window.chosen.row.period, in_sample.to_string());
+            assert_eq!(window.out_of_sample_row.strategy, window.chosen.strategy.name());
+        }
+
+        // the stitched curve compounds the out-of-sample returns
+        let compounded = walk
+            .windows
+            .iter()
+            .map(|window| 1. + window.out_of_sample_row.performance.total_return)
+            .product::<f64>();
+        let performance = walk.performance.as_ref().unwrap();
+        assert!((1. + performance.total_return - compounded).abs() < 1e-9);
+        assert_eq!(walk.compounded_capital[0].1, 1000.);
+        assert_eq!(walk.compounded_capital[0].0, walk.windows[0].out_of_sample_row.performance.start);
+        let (_, last) = walk.compounded_capital.last().unwrap();
+        assert!((last - 1000. * compounded).abs() < 1e-6);
+    }
+}